@@ -0,0 +1,375 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+use result::RegressionResult;
+
+/// Fits the line through `(x0, y0)` that minimizes the squared residuals
+///
+/// The sums of squares are taken around `(x0, y0)`, which yields the
+/// uncentered statistics of a regression through the origin for `(0, 0)`.
+fn fit_through_point<I, F>(xys: I, (x0, y0): (F, F)) -> Result<RegressionResult<F>, LinRegError>
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    let mut n = 0;
+    let mut x_sum = F::zero();
+    let mut y_sum = F::zero();
+
+    // SUM (x-x0)^2
+    let mut sxx = F::zero();
+
+    // SUM (y-y0)^2
+    let mut syy = F::zero();
+
+    // SUM (x-x0) (y-y0)
+    let mut sxy = F::zero();
+
+    for (x, y) in xys {
+        n += 1;
+        x_sum = x_sum + x;
+        y_sum = y_sum + y;
+
+        sxx = sxx + (x - x0) * (x - x0);
+        syy = syy + (y - y0) * (y - y0);
+        sxy = sxy + (x - x0) * (y - y0);
+    }
+
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+
+    let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+    RegressionResult::through_point(n, x_sum / n_f, y_sum / n_f, (x0, y0), sxx, syy, sxy)
+}
+
+/// Linear regression through the origin from two slices
+///
+/// Fits `y = slope * x` without an intercept, for data that is known to pass
+/// through `(0, 0)`. The returned [`RegressionResult`](struct.RegressionResult.html)
+/// has an intercept of zero, sums of squares around the origin, an
+/// uncentered R² and `n - 1` degrees of freedom. The intercept has a standard
+/// error of zero and no test statistics.
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * all x values are zero (`ConstantX`)
+/// * the slope is NaN or infinite (`NonFinite`)
+pub fn linear_regression_through_origin<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_through_point(
+        xs.iter()
+            .map(|x| x.clone().into())
+            .zip(ys.iter().map(|y| y.clone().into())),
+        (F::zero(), F::zero()),
+    )
+}
+
+/// Linear regression through the origin from tuples
+///
+/// The tuple counterpart of
+/// [`linear_regression_through_origin`](fn.linear_regression_through_origin.html).
+pub fn linear_regression_through_origin_of<X, Y, F>(
+    xys: &[(X, Y)],
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_through_point(
+        xys.iter()
+            .map(|(x, y)| (x.clone().into(), y.clone().into())),
+        (F::zero(), F::zero()),
+    )
+}
+
+/// Fits the best line with the given slope
+fn fit_with_slope<I, G, F>(xys: G, slope: F) -> Result<RegressionResult<F>, LinRegError>
+where
+    G: Fn() -> I,
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    let (n, x_sum, y_sum) = xys().fold((0, F::zero(), F::zero()), |(n, sx, sy), (x, y)| {
+        (n + 1, sx + x, sy + y)
+    });
+
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+
+    let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+    let x_mean = x_sum / n_f;
+    let y_mean = y_sum / n_f;
+
+    // SUM (x-mean(x))^2, SUM (y-mean(y))^2 and SUM (x-mean(x)) (y-mean(y))
+    let (sxx, syy, sxy) = xys().fold((F::zero(), F::zero(), F::zero()), |(xx, yy, xy), (x, y)| {
+        (
+            xx + (x - x_mean) * (x - x_mean),
+            yy + (y - y_mean) * (y - y_mean),
+            xy + (x - x_mean) * (y - y_mean),
+        )
+    });
+
+    RegressionResult::with_fixed_slope(n, x_mean, y_mean, slope, sxx, syy, sxy)
+}
+
+/// Linear regression through a given point from two slices
+///
+/// Fits the line through `(x0, y0)` with the smallest squared residuals, so
+/// only the slope is estimated. The returned
+/// [`RegressionResult`](struct.RegressionResult.html) has sums of squares
+/// around `(x0, y0)`, an R² that is uncentered around `y0` and `n - 1`
+/// degrees of freedom.
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * all x values equal `x0` (`ConstantX`)
+/// * the slope or intercept is NaN or infinite (`NonFinite`)
+pub fn linear_regression_through_point<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    (x0, y0): (F, F),
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_through_point(
+        xs.iter()
+            .map(|x| x.clone().into())
+            .zip(ys.iter().map(|y| y.clone().into())),
+        (x0, y0),
+    )
+}
+
+/// Linear regression through a given point from tuples
+///
+/// The tuple counterpart of
+/// [`linear_regression_through_point`](fn.linear_regression_through_point.html).
+pub fn linear_regression_through_point_of<X, Y, F>(
+    xys: &[(X, Y)],
+    (x0, y0): (F, F),
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_through_point(
+        xys.iter()
+            .map(|(x, y)| (x.clone().into(), y.clone().into())),
+        (x0, y0),
+    )
+}
+
+/// Linear regression with a known intercept from two slices
+///
+/// Fits only the slope of `y = slope * x + intercept`. This is the line
+/// through `(0, intercept)`, see
+/// [`linear_regression_through_point`](fn.linear_regression_through_point.html)
+/// for the statistics and possible errors.
+pub fn linear_regression_with_intercept<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    intercept: F,
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    linear_regression_through_point(xs, ys, (F::zero(), intercept))
+}
+
+/// Linear regression with a known intercept from tuples
+///
+/// The tuple counterpart of
+/// [`linear_regression_with_intercept`](fn.linear_regression_with_intercept.html).
+pub fn linear_regression_with_intercept_of<X, Y, F>(
+    xys: &[(X, Y)],
+    intercept: F,
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    linear_regression_through_point_of(xys, (F::zero(), intercept))
+}
+
+/// Linear regression with a known slope from two slices
+///
+/// Fits only the intercept of `y = slope * x + intercept`, which is
+/// `mean(y) - slope * mean(x)`. The returned
+/// [`RegressionResult`](struct.RegressionResult.html) has sums of squares
+/// around the means and `n - 1` degrees of freedom. The slope has a standard
+/// error of zero and no test statistics. R² compares against the mean of y
+/// and is negative if the slope fits worse than a horizontal line.
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * the intercept is NaN or infinite (`NonFinite`)
+pub fn linear_regression_with_slope<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    slope: F,
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_with_slope(
+        || {
+            xs.iter()
+                .map(|x| x.clone().into())
+                .zip(ys.iter().map(|y| y.clone().into()))
+        },
+        slope,
+    )
+}
+
+/// Linear regression with a known slope from tuples
+///
+/// The tuple counterpart of
+/// [`linear_regression_with_slope`](fn.linear_regression_with_slope.html).
+pub fn linear_regression_with_slope_of<X, Y, F>(
+    xys: &[(X, Y)],
+    slope: F,
+) -> Result<RegressionResult<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_with_slope(
+        || {
+            xys.iter()
+                .map(|(x, y)| (x.clone().into(), y.clone().into()))
+        },
+        slope,
+    )
+}
+
+#[test]
+fn test_through_origin() {
+    let xs: Vec<u8> = vec![1, 2, 3, 4];
+    let ys: Vec<f64> = vec![2.1, 3.9, 6.2, 7.8];
+
+    let r: RegressionResult<f64> = linear_regression_through_origin(&xs, &ys).unwrap();
+
+    assert!((r.slope - 1.99).abs() < 1e-12);
+    assert_eq!(0.0, r.intercept);
+    assert_eq!(3, r.df);
+    assert!((r.sse - 0.097).abs() < 1e-12);
+    assert!((r.r_squared - (1.0 - 0.097 / 118.9)).abs() < 1e-12);
+    assert!((r.slope_std_error - (0.097_f64 / 3.0 / 30.0).sqrt()).abs() < 1e-12);
+    assert_eq!(0.0, r.intercept_std_error);
+    assert!(r.intercept_t_value.is_nan());
+    assert!(r.slope_p_value < 1e-4);
+
+    // the uncertainty of the line vanishes at the origin
+    let ci = r.confidence_interval(0.0, 0.95).unwrap();
+    assert_eq!([0.0, 0.0], ci);
+
+    let tuples: Vec<(u8, f64)> = xs.iter().cloned().zip(ys.iter().cloned()).collect();
+    let r_of: RegressionResult<f64> = linear_regression_through_origin_of(&tuples).unwrap();
+    assert_eq!((r.slope, r.sse), (r_of.slope, r_of.sse));
+}
+
+#[test]
+fn test_through_origin_errors() {
+    let tuples: Vec<(f64, f64)> = vec![(0.0, 1.0), (0.0, 2.0)];
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        linear_regression_through_origin_of::<_, _, f64>(&tuples)
+    );
+
+    // a constant, nonzero x still determines the slope
+    let tuples: Vec<(f64, f64)> = vec![(2.0, 1.0), (2.0, 3.0)];
+    let r: RegressionResult<f64> = linear_regression_through_origin_of(&tuples).unwrap();
+    assert_eq!(1.0, r.slope);
+
+    let empty: Vec<f64> = vec![];
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        linear_regression_through_origin::<_, _, f64>(&empty, &empty)
+    );
+}
+
+#[test]
+fn test_through_point() {
+    let tuples: Vec<(f64, f64)> = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 5.0), (4.0, 4.0), (5.0, 5.0)];
+
+    // the least squares line passes through the means anyway
+    let r: RegressionResult<f64> = linear_regression_through_point_of(&tuples, (3.0, 4.0)).unwrap();
+    assert!((r.slope - 0.6).abs() < 1e-12);
+    assert!((r.intercept - 2.2).abs() < 1e-12);
+    assert_eq!(4, r.df);
+    assert!((r.slope_std_error - (2.4_f64 / 4.0 / 10.0).sqrt()).abs() < 1e-12);
+    assert!((r.intercept_std_error - 3.0 * r.slope_std_error).abs() < 1e-12);
+
+    let ci = r.confidence_interval(3.0, 0.95).unwrap();
+    assert!((ci[0] - 4.0).abs() < 1e-12 && (ci[1] - 4.0).abs() < 1e-12);
+
+    let xs: Vec<f64> = tuples.iter().map(|t| t.0).collect();
+    let ys: Vec<f64> = tuples.iter().map(|t| t.1).collect();
+    let r: RegressionResult<f64> = linear_regression_with_intercept(&xs, &ys, 1.0).unwrap();
+    // SUM x (y-1) / SUM x^2 = 51 / 55
+    assert!((r.slope - 51.0 / 55.0).abs() < 1e-12);
+    assert_eq!(1.0, r.intercept);
+    assert_eq!(0.0, r.intercept_std_error);
+}
+
+#[test]
+fn test_with_slope() {
+    let xs: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    let ys: Vec<f64> = vec![2.0, 4.0, 5.0, 4.0, 5.0];
+
+    let r: RegressionResult<f64> = linear_regression_with_slope(&xs, &ys, 1.0).unwrap();
+    assert_eq!(1.0, r.slope);
+    assert_eq!(1.0, r.intercept);
+    assert_eq!(4, r.df);
+    // SUM (y - x - 1)^2
+    assert!((r.sse - 4.0).abs() < 1e-12);
+    assert!((r.r_squared - (1.0 - 4.0 / 6.0)).abs() < 1e-12);
+    assert_eq!(0.0, r.slope_std_error);
+    assert!(r.slope_t_value.is_nan());
+    assert!((r.intercept_std_error - (1.0_f64 / 5.0).sqrt()).abs() < 1e-12);
+
+    // a constant x is fine when the slope is known
+    let tuples: Vec<(f64, f64)> = vec![(2.0, 1.0), (2.0, 3.0)];
+    let r: RegressionResult<f64> = linear_regression_with_slope_of(&tuples, 0.5).unwrap();
+    assert_eq!(1.0, r.intercept);
+}
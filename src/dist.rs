@@ -0,0 +1,260 @@
+//! Probability distributions needed for significance tests
+//!
+//! Everything here only uses `num_traits::Float`, so it works without libstd.
+
+use num_traits::Float;
+
+/// Converts a constant into `F`
+pub(crate) fn constant<F: Float>(value: f64) -> F {
+    F::from(value).expect("constant not representable as float")
+}
+
+/// Natural logarithm of the gamma function for `x > 0`
+///
+/// Uses the Lanczos approximation with `g = 7`.
+fn ln_gamma<F: Float>(x: F) -> F {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    let half = constant::<F>(0.5);
+    let x = x - F::one();
+    let t = x + constant(7.5);
+
+    let mut sum = constant::<F>(COEFFICIENTS[0]);
+    let mut denominator = x;
+    for &c in &COEFFICIENTS[1..] {
+        denominator = denominator + F::one();
+        sum = sum + constant::<F>(c) / denominator;
+    }
+
+    // ln(sqrt(2 pi))
+    constant::<F>(0.918_938_533_204_672_8) + (x + half) * t.ln() - t + sum.ln()
+}
+
+/// Continued fraction for the incomplete beta function, evaluated with
+/// the modified Lentz method
+fn beta_continued_fraction<F: Float>(a: F, b: F, x: F) -> F {
+    const MAX_ITERATIONS: usize = 300;
+
+    let one = F::one();
+    let two = one + one;
+    let tiny = constant::<F>(1e-30);
+    let eps = F::epsilon();
+
+    let clamp = |v: F| if v.abs() < tiny { tiny } else { v };
+
+    let mut c = one;
+    let mut d = one / clamp(one - (a + b) * x / (a + one));
+    let mut h = d;
+
+    let mut m = one;
+    for _ in 0..MAX_ITERATIONS {
+        let m2 = two * m;
+
+        // even step
+        let num = m * (b - m) * x / ((a + m2 - one) * (a + m2));
+        d = one / clamp(one + num * d);
+        c = clamp(one + num / c);
+        h = h * d * c;
+
+        // odd step
+        let num = -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + one));
+        d = one / clamp(one + num * d);
+        c = clamp(one + num / c);
+        let delta = d * c;
+        h = h * delta;
+
+        if (delta - one).abs() <= eps {
+            break;
+        }
+        m = m + one;
+    }
+
+    h
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`
+fn incomplete_beta<F: Float>(a: F, b: F, x: F) -> F {
+    if x <= F::zero() {
+        return F::zero();
+    }
+    if x >= F::one() {
+        return F::one();
+    }
+
+    let one = F::one();
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (one - x).ln();
+    let front = ln_front.exp();
+
+    // the continued fraction converges quickly only below this point
+    if x < (a + one) / (a + b + one + one) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        one - front * beta_continued_fraction(b, a, one - x) / b
+    }
+}
+
+/// Two-sided p-value of a t-statistic with `df` degrees of freedom
+pub(crate) fn students_t_two_sided_p<F: Float>(t: F, df: F) -> F {
+    if t.is_nan() || df.is_nan() {
+        return F::nan();
+    }
+
+    let half = constant::<F>(0.5);
+    incomplete_beta(half * df, half, df / (df + t * t))
+}
+
+/// Quantile function of Student's t-distribution with `df` degrees of
+/// freedom, for `0 < p < 1`
+///
+/// Inverts the distribution function by bisection, which is slow but only
+/// needs the incomplete beta function.
+pub(crate) fn students_t_quantile<F: Float>(p: F, df: F) -> F {
+    let half = constant::<F>(0.5);
+
+    if !(p > F::zero() && p < F::one()) || df.is_nan() {
+        return F::nan();
+    }
+    if p == half {
+        return F::zero();
+    }
+    if p < half {
+        return -students_t_quantile(F::one() - p, df);
+    }
+
+    // solve for the t where both tails together hold the remaining mass
+    let alpha = (F::one() - p) * constant(2.0);
+
+    let mut lo = F::zero();
+    let mut hi = F::one();
+    while students_t_two_sided_p(hi, df) > alpha {
+        lo = hi;
+        hi = hi * constant(2.0);
+        if !hi.is_finite() {
+            return hi;
+        }
+    }
+
+    loop {
+        let mid = lo + (hi - lo) * half;
+        if mid <= lo || mid >= hi {
+            return mid;
+        }
+
+        if students_t_two_sided_p(mid, df) > alpha {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+}
+
+/// Quantile function of the standard normal distribution, for `0 < p < 1`
+///
+/// Uses Acklam's rational approximation, which has a relative error below
+/// `1.2e-9`.
+pub(crate) fn normal_quantile<F: Float>(p: F) -> F {
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+
+    if !(p > F::zero() && p < F::one()) {
+        return F::nan();
+    }
+
+    let polynomial = |coefficients: &[f64], x: F| {
+        coefficients
+            .iter()
+            .fold(F::zero(), |sum, &c| sum * x + constant(c))
+    };
+    let tail = |q: F| {
+        let r = (constant::<F>(-2.0) * q.ln()).sqrt();
+        polynomial(&C, r) / (polynomial(&D, r) * r + F::one())
+    };
+
+    let low = constant::<F>(0.024_25);
+    if p < low {
+        tail(p)
+    } else if p > F::one() - low {
+        -tail(F::one() - p)
+    } else {
+        let q = p - constant(0.5);
+        let r = q * q;
+        polynomial(&A, r) * q / (polynomial(&B, r) * r + F::one())
+    }
+}
+
+#[test]
+fn test_students_t_two_sided_p() {
+    assert!((students_t_two_sided_p(2.0, 3.0) - 0.139_325_968_558_849).abs() < 1e-10);
+    assert!((students_t_two_sided_p(-2.0, 3.0) - 0.139_325_968_558_849).abs() < 1e-10);
+    assert_eq!(0.0, students_t_two_sided_p(f64::INFINITY, 3.0));
+}
+
+#[test]
+fn test_students_t_quantile() {
+    let cases: [(f64, f64, f64); 4] = [
+        (0.975, 3.0, 3.182_446_305_284_263),
+        (0.975, 10.0, 2.228_138_851_986_274),
+        (0.95, 1.0, 6.313_751_514_675_041),
+        (0.005, 30.0, -2.749_995_653_567_871),
+    ];
+
+    for &(p, df, expected) in &cases {
+        assert!((students_t_quantile(p, df) - expected).abs() < 1e-9);
+    }
+
+    assert_eq!(0.0, students_t_quantile(0.5, 4.0));
+    assert!(students_t_quantile(1.0, 4.0).is_nan());
+}
+
+#[test]
+fn test_normal_quantile() {
+    let cases: [(f64, f64); 4] = [
+        (0.975, 1.959_963_984_540_054),
+        (0.5, 0.0),
+        (0.01, -2.326_347_874_040_841),
+        (0.999_9, 3.719_016_485_455_709),
+    ];
+
+    for &(p, expected) in &cases {
+        assert!((normal_quantile(p) - expected).abs() < 1e-8);
+    }
+
+    assert!(normal_quantile(0.0f64).is_nan());
+}
@@ -0,0 +1,496 @@
+use num_traits::Float;
+
+use alloc::vec::Vec;
+
+use dist::constant;
+use error::LinRegError;
+use stats::mean;
+
+/// Lasso and elastic-net regression by coordinate descent
+///
+/// Minimizes
+///
+/// ```text
+/// 1/(2n) SUM (y - b0 - x·beta)^2 + lambda (alpha |beta|_1 + (1-alpha)/2 |beta|^2)
+/// ```
+///
+/// over the intercept `b0` and the coefficients `beta`. The L1 part drives
+/// coefficients of irrelevant predictors to exactly zero, the L2 part keeps
+/// groups of correlated predictors together. `alpha = 1` is the lasso and
+/// `alpha = 0` is ridge regression. The intercept is not penalized.
+///
+/// The predictors are not standardized, so scale them to comparable units
+/// first if the penalty should treat them alike. Requires the `alloc`
+/// feature.
+///
+/// ```rust
+/// use linreg::ElasticNet;
+///
+/// let rows = [[1.0, 0.2], [2.0, -0.1], [3.0, 0.3], [4.0, 0.0], [5.0, -0.2]];
+/// let ys = [2.1, 3.9, 6.1, 8.0, 9.9];
+///
+/// let lasso = ElasticNet::lasso();
+/// let lambda_max = lasso.lambda_max(&rows, &ys).unwrap();
+/// let lambdas = [lambda_max, 0.5 * lambda_max, 0.01 * lambda_max];
+///
+/// let path = lasso.path(&rows, &ys, &lambdas).unwrap();
+/// assert_eq!(vec![0.0, 0.0], path[0].coefficients);
+/// assert!(path[1].coefficients[0] > 0.0);
+/// assert_eq!(0.0, path[1].coefficients[1]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ElasticNet<F> {
+    alpha: F,
+    tolerance: F,
+    max_iterations: usize,
+}
+
+/// Coefficients of an elastic-net regression at one penalty
+#[derive(Clone, Debug, PartialEq)]
+pub struct ElasticNetFit<F> {
+    /// Penalty the coefficients were fitted with
+    pub lambda: F,
+    /// Unpenalized intercept
+    pub intercept: F,
+    /// Fitted coefficients, in the order of the predictors
+    pub coefficients: Vec<F>,
+    /// Coordinate descent sweeps needed to converge
+    pub iterations: usize,
+}
+
+impl<F: Float> ElasticNetFit<F> {
+    /// Evaluates the fitted model for the predictors `row`
+    ///
+    /// Extra or missing predictors are ignored.
+    pub fn predict(&self, row: &[F]) -> F {
+        row.iter()
+            .zip(self.coefficients.iter())
+            .fold(self.intercept, |sum, (&x, &b)| sum + x * b)
+    }
+
+    /// Number of nonzero coefficients
+    pub fn nonzero(&self) -> usize {
+        self.coefficients
+            .iter()
+            .filter(|&&b| b != F::zero())
+            .count()
+    }
+}
+
+/// Centered cross products of the predictors and the response, divided by
+/// the number of data points
+struct Gram<F> {
+    p: usize,
+    x_means: Vec<F>,
+    y_mean: F,
+    // row-major p x p
+    xx: Vec<F>,
+    xy: Vec<F>,
+    yy: F,
+}
+
+impl<F: Float> Gram<F> {
+    fn new<R, X, Y>(rows: &[R], ys: &[Y]) -> Result<Self, LinRegError>
+    where
+        R: AsRef<[X]>,
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        if rows.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+        if rows.is_empty() {
+            return Err(LinRegError::EmptyInput);
+        }
+
+        let p = rows[0].as_ref().len();
+        if rows.iter().any(|row| row.as_ref().len() != p) {
+            return Err(LinRegError::LengthMismatch);
+        }
+        let n = F::from(rows.len()).ok_or(LinRegError::CountNotRepresentable)?;
+
+        let mut x_means: Vec<F> = vec![F::zero(); p];
+        for (j, m) in x_means.iter_mut().enumerate() {
+            *m = mean(rows.iter().map(|row| row.as_ref()[j].clone().into()))?.1;
+        }
+        let (_, y_mean) = mean(ys.iter().map(|y| y.clone().into()))?;
+
+        let mut xx: Vec<F> = vec![F::zero(); p * p];
+        let mut xy: Vec<F> = vec![F::zero(); p];
+        let mut yy = F::zero();
+        let mut centered: Vec<F> = vec![F::zero(); p];
+        for (row, y) in rows.iter().zip(ys.iter()) {
+            for ((c, x), &m) in centered.iter_mut().zip(row.as_ref()).zip(x_means.iter()) {
+                *c = x.clone().into() - m;
+            }
+            let dy = y.clone().into() - y_mean;
+
+            for j in 0..p {
+                for k in j..p {
+                    xx[j * p + k] = xx[j * p + k] + centered[j] * centered[k];
+                }
+                xy[j] = xy[j] + centered[j] * dy;
+            }
+            yy = yy + dy * dy;
+        }
+
+        for j in 0..p {
+            for k in j..p {
+                xx[j * p + k] = xx[j * p + k] / n;
+                xx[k * p + j] = xx[j * p + k];
+            }
+            xy[j] = xy[j] / n;
+        }
+        yy = yy / n;
+
+        let finite = |v: &F| v.is_finite();
+        if !(yy.is_finite() && xx.iter().all(finite) && xy.iter().all(finite)) {
+            return Err(LinRegError::NonFinite);
+        }
+
+        Ok(Gram {
+            p,
+            x_means,
+            y_mean,
+            xx,
+            xy,
+            yy,
+        })
+    }
+}
+
+/// Shrinks `z` towards zero by `gamma`
+fn soft_threshold<F: Float>(z: F, gamma: F) -> F {
+    if z > gamma {
+        z - gamma
+    } else if z < -gamma {
+        z + gamma
+    } else {
+        F::zero()
+    }
+}
+
+impl<F: Float> ElasticNet<F> {
+    /// Creates an estimator that mixes the lasso and ridge penalties
+    ///
+    /// `alpha` must lie in `[0, 1]`; it is the share of the L1 penalty.
+    /// Returns `InvalidParameter` otherwise.
+    pub fn new(alpha: F) -> Result<Self, LinRegError> {
+        if alpha.is_nan() || alpha < F::zero() || alpha > F::one() {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        Ok(ElasticNet {
+            alpha,
+            tolerance: constant(1e-7),
+            max_iterations: 10_000,
+        })
+    }
+
+    /// Creates a lasso estimator, which uses the L1 penalty only
+    pub fn lasso() -> Self {
+        ElasticNet {
+            alpha: F::one(),
+            tolerance: constant(1e-7),
+            max_iterations: 10_000,
+        }
+    }
+
+    /// Sets the convergence tolerance, `1e-7` by default
+    ///
+    /// A sweep has converged once no coefficient moved the fitted values
+    /// by more than this share of the variance of y.
+    pub fn with_tolerance(mut self, tolerance: F) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the maximum number of coordinate descent sweeps per penalty,
+    /// 10 000 by default
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Share of the L1 penalty
+    pub fn alpha(&self) -> F {
+        self.alpha
+    }
+
+    /// Smallest penalty at which all coefficients are zero
+    ///
+    /// Useful as the start of a regularization path. Fails for the reasons
+    /// listed under [`path`](#method.path), or with `InvalidParameter` if
+    /// `alpha` is zero, since a pure ridge penalty never zeroes coefficients.
+    pub fn lambda_max<R, X, Y>(&self, rows: &[R], ys: &[Y]) -> Result<F, LinRegError>
+    where
+        R: AsRef<[X]>,
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        if self.alpha == F::zero() {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        let gram = Gram::new(rows, ys)?;
+        let max = gram.xy.iter().fold(F::zero(), |m, c| m.max(c.abs()));
+        Ok(max / self.alpha)
+    }
+
+    /// Fits the model at a single penalty
+    ///
+    /// Like [`path`](#method.path) with one `lambda`.
+    pub fn fit<R, X, Y>(
+        &self,
+        rows: &[R],
+        ys: &[Y],
+        lambda: F,
+    ) -> Result<ElasticNetFit<F>, LinRegError>
+    where
+        R: AsRef<[X]>,
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        let mut path = self.path(rows, ys, &[lambda])?;
+        Ok(path.remove(0))
+    }
+
+    /// Fits the model along a regularization path
+    ///
+    /// Returns one fit per entry of `lambdas`, in the same order. Each fit
+    /// starts from the coefficients of the previous one, so a decreasing
+    /// sequence such as a geometric grid below
+    /// [`lambda_max`](#method.lambda_max) converges much faster than
+    /// separate fits. `rows` may hold arrays or vectors of predictors.
+    ///
+    /// Returns an error if
+    ///
+    /// * `rows` and `ys` or two rows differ in length (`LengthMismatch`)
+    /// * `rows` is empty (`EmptyInput`)
+    /// * `lambdas` is empty or holds a negative, NaN or infinite value, or
+    ///   the tolerance is not positive (`InvalidParameter`)
+    /// * a NaN or infinite value occurred (`NonFinite`)
+    /// * a fit needs more sweeps than allowed (`NotConverged`)
+    pub fn path<R, X, Y>(
+        &self,
+        rows: &[R],
+        ys: &[Y],
+        lambdas: &[F],
+    ) -> Result<Vec<ElasticNetFit<F>>, LinRegError>
+    where
+        R: AsRef<[X]>,
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        if lambdas.is_empty() || self.tolerance.is_nan() || self.tolerance <= F::zero() {
+            return Err(LinRegError::InvalidParameter);
+        }
+        if lambdas.iter().any(|&l| !l.is_finite() || l < F::zero()) {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        let gram = Gram::new(rows, ys)?;
+        let mut beta: Vec<F> = vec![F::zero(); gram.p];
+        // xy - xx beta, kept up to date with every coordinate step
+        let mut gradient = gram.xy.clone();
+
+        let mut fits = Vec::with_capacity(lambdas.len());
+        for &lambda in lambdas {
+            let iterations = self.descend(&gram, lambda, &mut beta, &mut gradient)?;
+
+            let intercept = gram
+                .x_means
+                .iter()
+                .zip(beta.iter())
+                .fold(gram.y_mean, |b0, (&m, &b)| b0 - m * b);
+
+            fits.push(ElasticNetFit {
+                lambda,
+                intercept,
+                coefficients: beta.clone(),
+                iterations,
+            });
+        }
+
+        Ok(fits)
+    }
+
+    /// Runs coordinate descent sweeps until the coefficients settle
+    ///
+    /// Returns the number of sweeps.
+    fn descend(
+        &self,
+        gram: &Gram<F>,
+        lambda: F,
+        beta: &mut [F],
+        gradient: &mut [F],
+    ) -> Result<usize, LinRegError> {
+        let p = gram.p;
+        let l1 = lambda * self.alpha;
+        let l2 = lambda * (F::one() - self.alpha);
+        let threshold = self.tolerance * gram.yy;
+
+        for iteration in 1..=self.max_iterations {
+            let mut max_change = F::zero();
+
+            for j in 0..p {
+                let xx_jj = gram.xx[j * p + j];
+                let old = beta[j];
+
+                // correlation of x_j with the residual of all other predictors
+                let z = gradient[j] + xx_jj * old;
+                let denominator = xx_jj + l2;
+                let new = if denominator > F::zero() {
+                    soft_threshold(z, l1) / denominator
+                } else {
+                    F::zero()
+                };
+
+                if new != old {
+                    let delta = new - old;
+                    for (k, g) in gradient.iter_mut().enumerate() {
+                        *g = *g - gram.xx[k * p + j] * delta;
+                    }
+                    beta[j] = new;
+                    max_change = max_change.max(xx_jj * delta * delta);
+                }
+            }
+
+            if !max_change.is_finite() {
+                return Err(LinRegError::NonFinite);
+            }
+            if max_change <= threshold {
+                return Ok(iteration);
+            }
+        }
+
+        Err(LinRegError::NotConverged)
+    }
+}
+
+#[test]
+fn test_lasso_without_penalty_matches_ols() {
+    let data = [
+        ([0.3, 2.0], 1.2),
+        ([1.1, 0.5], 2.9),
+        ([2.4, 1.5], 3.1),
+        ([3.0, 3.5], 1.0),
+        ([4.2, 0.1], 7.7),
+        ([5.5, 2.2], 6.0),
+    ];
+    let rows: Vec<[f64; 2]> = data.iter().map(|&(row, _)| row).collect();
+    let ys: Vec<f64> = data.iter().map(|&(_, y)| y).collect();
+
+    let mut ols = ::MultiRegression::<f64, 3>::new();
+    for &([x1, x2], y) in &data {
+        ols.push([1.0, x1, x2], y);
+    }
+    let ols = ols.fit().unwrap();
+
+    let fit = ElasticNet::lasso()
+        .with_tolerance(1e-24)
+        .fit(&rows, &ys, 0.0)
+        .unwrap();
+
+    assert!((fit.intercept - ols.coefficients[0]).abs() < 1e-9);
+    assert!((fit.coefficients[0] - ols.coefficients[1]).abs() < 1e-9);
+    assert!((fit.coefficients[1] - ols.coefficients[2]).abs() < 1e-9);
+    assert!((fit.predict(&[1.0, 1.0]) - ols.predict(&[1.0, 1.0, 1.0])).abs() < 1e-9);
+}
+
+#[test]
+fn test_elastic_net_ridge_limit() {
+    let rows: Vec<Vec<f64>> = vec![
+        vec![0.3, 2.0],
+        vec![1.1, 0.5],
+        vec![2.4, 1.5],
+        vec![3.0, 3.5],
+        vec![4.2, 0.1],
+    ];
+    let ys: Vec<f64> = vec![1.2, 2.9, 3.1, 1.0, 7.7];
+    let lambda = 0.3;
+
+    // 1/(2n) RSS + lambda/2 |beta|^2 is the ridge objective RSS + n lambda |beta|^2
+    let mut reg = ::MultiRegression::<f64, 3>::new();
+    for (row, &y) in rows.iter().zip(ys.iter()) {
+        reg.push([1.0, row[0], row[1]], y);
+    }
+    let ridge = reg.fit_ridge(5.0 * lambda, &[false, true, true]).unwrap();
+
+    let fit = ElasticNet::new(0.0)
+        .unwrap()
+        .with_tolerance(1e-24)
+        .fit(&rows, &ys, lambda)
+        .unwrap();
+
+    assert!((fit.intercept - ridge.coefficients[0]).abs() < 1e-9);
+    assert!((fit.coefficients[0] - ridge.coefficients[1]).abs() < 1e-9);
+    assert!((fit.coefficients[1] - ridge.coefficients[2]).abs() < 1e-9);
+}
+
+#[test]
+fn test_lasso_path() {
+    let rows: Vec<[f64; 3]> = (0..20)
+        .map(|i| {
+            let t = f64::from(i);
+            [t, (t * 0.7).sin(), (t * 1.3).cos()]
+        })
+        .collect();
+    let ys: Vec<f64> = rows.iter().map(|r| 3.0 + 0.5 * r[0] + 2.0 * r[1]).collect();
+
+    let lasso = ElasticNet::lasso();
+    let lambda_max = lasso.lambda_max(&rows, &ys).unwrap();
+    let lambdas: Vec<f64> = (0..30).map(|i| lambda_max * 0.7f64.powi(i)).collect();
+    let path = lasso.path(&rows, &ys, &lambdas).unwrap();
+
+    assert_eq!(30, path.len());
+    assert_eq!(0, path[0].nonzero());
+    let y_mean = ys.iter().sum::<f64>() / 20.0;
+    assert!((path[0].intercept - y_mean).abs() < 1e-12);
+
+    // the coefficients enter one by one, and the irrelevant one stays small
+    assert!(path
+        .windows(2)
+        .all(|w| w[0].nonzero() <= w[1].nonzero() + 1));
+    let last = &path[29];
+    assert!((last.coefficients[0] - 0.5).abs() < 1e-3);
+    assert!((last.coefficients[1] - 2.0).abs() < 1e-2);
+    assert!(last.coefficients[2].abs() < 1e-2);
+}
+
+#[test]
+fn test_elastic_net_errors() {
+    let rows: Vec<Vec<f64>> = vec![vec![1.0, 2.0], vec![2.0]];
+    let ys: Vec<f64> = vec![1.0, 2.0];
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ElasticNet::<f64>::new(1.5)
+    );
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        ElasticNet::lasso().fit(&rows, &ys, 0.1)
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        ElasticNet::lasso().fit(&rows[..0], &ys[..0], 0.1)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ElasticNet::<f64>::lasso().path(&rows[..1], &ys[..1], &[])
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ElasticNet::new(0.0)
+            .unwrap()
+            .lambda_max(&rows[..1], &ys[..1])
+    );
+
+    let rows: Vec<[f64; 2]> = vec![[0.0, 1.0], [1.0, 0.0], [2.0, 2.0]];
+    assert_eq!(
+        Err(LinRegError::NotConverged),
+        ElasticNet::lasso()
+            .with_max_iterations(1)
+            .fit(&rows, &[1.0, 0.0, 3.0], 0.0)
+    );
+}
@@ -0,0 +1,53 @@
+use core::fmt;
+
+/// Reasons why a regression could not be calculated
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LinRegError {
+    /// The x and y inputs differ in length
+    LengthMismatch,
+    /// No data points were given
+    EmptyInput,
+    /// All x values are identical, so the slope is undefined
+    ConstantX,
+    /// The number of data points cannot be represented as the float type
+    CountNotRepresentable,
+    /// A NaN or infinite value occurred during the calculation
+    NonFinite,
+    /// There are too few data points for the requested statistic
+    TooFewPoints,
+    /// A parameter, such as a confidence level, lies outside its valid range
+    InvalidParameter,
+    /// A weight is negative
+    NegativeWeight,
+    /// All weights are zero
+    ZeroWeights,
+    /// The predictors are linearly dependent, so the coefficients are not
+    /// unique
+    RankDeficient,
+    /// An iterative fit reached its iteration limit before converging
+    NotConverged,
+}
+
+impl fmt::Display for LinRegError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            LinRegError::LengthMismatch => "x and y values differ in length",
+            LinRegError::EmptyInput => "no data points given",
+            LinRegError::ConstantX => "all x values are identical",
+            LinRegError::CountNotRepresentable => {
+                "number of data points cannot be represented as a float"
+            }
+            LinRegError::NonFinite => "a NaN or infinite value occurred",
+            LinRegError::TooFewPoints => "too few data points",
+            LinRegError::InvalidParameter => "parameter out of range",
+            LinRegError::NegativeWeight => "negative weight",
+            LinRegError::ZeroWeights => "all weights are zero",
+            LinRegError::RankDeficient => "predictors are linearly dependent",
+            LinRegError::NotConverged => "iteration limit reached before convergence",
+        };
+        f.write_str(msg)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LinRegError {}
@@ -0,0 +1,212 @@
+use num_traits::Float;
+
+use dist::constant;
+use error::LinRegError;
+
+/// Linear regression that gradually forgets old data points
+///
+/// Every new sample multiplies the weight of all earlier samples by `decay`,
+/// so a point pushed `k` samples ago has the weight `decay^k`. The weighted
+/// means and co-moments are updated in O(1) per sample, without allocation.
+///
+/// ```rust
+/// use linreg::ExponentialRegression;
+///
+/// let mut reg = ExponentialRegression::with_half_life(10.0).unwrap();
+/// for i in 0..100 {
+///     let x = f64::from(i);
+///     reg.push(x, 2.0 * x + 1.0);
+/// }
+///
+/// assert!((reg.slope().unwrap() - 2.0).abs() < 1e-9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExponentialRegression<F> {
+    decay: F,
+    // sum of the current weights of all samples
+    weight: F,
+    x_mean: F,
+    y_mean: F,
+    // SUM w (x-mean(x))^2
+    sxx: F,
+    // SUM w (y-mean(y))^2
+    syy: F,
+    // SUM w (x-mean(x)) (y-mean(y))
+    sxy: F,
+}
+
+impl<F: Float> ExponentialRegression<F> {
+    /// Creates an accumulator with the given decay factor
+    ///
+    /// `decay` must lie in `(0, 1]`; smaller values forget faster and a value
+    /// of one weights all samples equally. Returns `InvalidParameter`
+    /// otherwise.
+    pub fn new(decay: F) -> Result<Self, LinRegError> {
+        if !(decay > F::zero() && decay <= F::one()) {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        Ok(ExponentialRegression {
+            decay,
+            weight: F::zero(),
+            x_mean: F::zero(),
+            y_mean: F::zero(),
+            sxx: F::zero(),
+            syy: F::zero(),
+            sxy: F::zero(),
+        })
+    }
+
+    /// Creates an accumulator in which a sample loses half of its weight
+    /// after `half_life` further samples
+    ///
+    /// `half_life` must be positive; it does not need to be an integer.
+    /// Returns `InvalidParameter` otherwise.
+    pub fn with_half_life(half_life: F) -> Result<Self, LinRegError> {
+        if half_life.is_nan() || half_life <= F::zero() {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        // decay^half_life = 1/2
+        let ln_2 = constant::<F>(2.0).ln();
+        ExponentialRegression::new((-ln_2 / half_life).exp())
+    }
+
+    /// Adds a data point with a weight of one, after decaying all earlier
+    /// points
+    pub fn push(&mut self, x: F, y: F) {
+        self.weight = self.weight * self.decay + F::one();
+        self.sxx = self.sxx * self.decay;
+        self.syy = self.syy * self.decay;
+        self.sxy = self.sxy * self.decay;
+
+        let dx = x - self.x_mean;
+        let dy = y - self.y_mean;
+        self.x_mean = self.x_mean + dx / self.weight;
+        self.y_mean = self.y_mean + dy / self.weight;
+
+        self.sxx = self.sxx + dx * (x - self.x_mean);
+        self.syy = self.syy + dy * (y - self.y_mean);
+        self.sxy = self.sxy + dx * (y - self.y_mean);
+    }
+
+    /// Decay factor applied per sample
+    pub fn decay(&self) -> F {
+        self.decay
+    }
+
+    /// Sum of the current sample weights, i.e. the effective number of
+    /// samples
+    ///
+    /// Approaches `1 / (1 - decay)` as samples keep coming in.
+    pub fn effective_count(&self) -> F {
+        self.weight
+    }
+
+    /// Weighted mean of the x values
+    pub fn x_mean(&self) -> F {
+        self.x_mean
+    }
+
+    /// Weighted mean of the y values
+    pub fn y_mean(&self) -> F {
+        self.y_mean
+    }
+
+    /// Slope of the weighted regression line
+    ///
+    /// Returns an error if no points were pushed (`EmptyInput`), all x values
+    /// are identical (`ConstantX`) or the slope is not finite (`NonFinite`).
+    pub fn slope(&self) -> Result<F, LinRegError> {
+        self.line().map(|(slope, _)| slope)
+    }
+
+    /// Intercept of the weighted regression line
+    ///
+    /// Fails for the same reasons as [`slope`](#method.slope).
+    pub fn intercept(&self) -> Result<F, LinRegError> {
+        self.line().map(|(_, intercept)| intercept)
+    }
+
+    /// Weighted coefficient of determination
+    ///
+    /// Fails for the same reasons as [`slope`](#method.slope). Is `NaN` if
+    /// all y values are identical.
+    pub fn r_squared(&self) -> Result<F, LinRegError> {
+        let (slope, _) = self.line()?;
+        Ok(slope * self.sxy / self.syy)
+    }
+
+    /// Slope and intercept of the weighted regression line
+    fn line(&self) -> Result<(F, F), LinRegError> {
+        if self.weight == F::zero() {
+            return Err(LinRegError::EmptyInput);
+        }
+        if self.sxx == F::zero() {
+            return Err(LinRegError::ConstantX);
+        }
+
+        let slope = self.sxy / self.sxx;
+        let intercept = self.y_mean - slope * self.x_mean;
+
+        if !slope.is_finite() || !intercept.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+
+        Ok((slope, intercept))
+    }
+}
+
+#[test]
+fn test_no_decay_matches_batch() {
+    let points = [(1.0, 2.0), (2.0, 4.0), (3.0, 5.0), (4.0, 4.0), (5.0, 5.0)];
+
+    let mut reg = ExponentialRegression::new(1.0).unwrap();
+    for &(x, y) in &points {
+        reg.push(x, y);
+    }
+
+    assert_eq!(5.0, reg.effective_count());
+    assert!((reg.slope().unwrap() - 0.6).abs() < 1e-12);
+    assert!((reg.intercept().unwrap() - 2.2).abs() < 1e-12);
+    assert!((reg.r_squared().unwrap() - 0.6).abs() < 1e-12);
+}
+
+#[test]
+fn test_decay_matches_weighted() {
+    let points = [(1.0, 2.0), (2.0, 4.0), (3.0, 5.0), (4.0, 4.0), (5.0, 5.0)];
+    let decay = 0.8;
+
+    let mut reg = ExponentialRegression::new(decay).unwrap();
+    for &(x, y) in &points {
+        reg.push(x, y);
+    }
+
+    let weighted = [
+        (1.0, 2.0, 0.4096),
+        (2.0, 4.0, 0.512),
+        (3.0, 5.0, 0.64),
+        (4.0, 4.0, 0.8),
+        (5.0, 5.0, 1.0),
+    ];
+    let (slope, intercept): (f64, f64) = ::weighted_linear_regression_of(&weighted).unwrap();
+
+    assert!((reg.slope().unwrap() - slope).abs() < 1e-12);
+    assert!((reg.intercept().unwrap() - intercept).abs() < 1e-12);
+}
+
+#[test]
+fn test_half_life() {
+    let reg = ExponentialRegression::<f64>::with_half_life(4.0).unwrap();
+    assert!((reg.decay().powi(4) - 0.5).abs() < 1e-12);
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ExponentialRegression::<f64>::with_half_life(0.0)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ExponentialRegression::<f64>::new(1.5)
+    );
+    assert_eq!(Err(LinRegError::EmptyInput), reg.slope());
+}
@@ -0,0 +1,347 @@
+use num_traits::Float;
+
+use core::cmp::Ordering;
+
+use dist::constant;
+use error::LinRegError;
+#[cfg(test)]
+use ransac::{uniform, SplitMix64};
+
+/// Check loss of a residual: `tau` times positive and `1 - tau` times
+/// negative residuals
+fn check_loss<F: Float>(r: F, tau: F) -> F {
+    if r < F::zero() {
+        (tau - F::one()) * r
+    } else {
+        tau * r
+    }
+}
+
+/// Sum of the check losses of the residuals of the line through point
+/// `pivot` with slope `slope`
+fn cost<G, F>(point: &G, n: usize, tau: F, pivot: usize, slope: F) -> F
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    let (xk, yk) = point(pivot);
+    (0..n).fold(F::zero(), |sum, i| {
+        let (x, y) = point(i);
+        sum + check_loss(y - yk - slope * (x - xk), tau)
+    })
+}
+
+/// Best line through point `pivot`
+///
+/// Minimizing `Σ |yi - yk - b (xi - xk)|` over the slope `b` is the same as
+/// minimizing `Σ |xi - xk| |si - b|`, where `si` is the slope from the pivot
+/// to point `i`, so the best slope is the weighted median of the `si`. For
+/// the check loss, the points left of the pivot count towards quantile
+/// `1 - tau` instead of `tau`, and the slope is a weighted quantile. If it
+/// falls between two slopes, the smaller one is taken.
+///
+/// `slopes` must hold `n` pairs. Returns `(slope, cost)`.
+fn best_through<G, F>(point: &G, n: usize, tau: F, pivot: usize, slopes: &mut [(F, F)]) -> (F, F)
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    let (xk, yk) = point(pivot);
+
+    let mut m = 0;
+    let mut target = F::zero();
+    for i in 0..n {
+        let (x, y) = point(i);
+        if x != xk {
+            let dx = x - xk;
+            slopes[m] = ((y - yk) / dx, dx.abs());
+            target = target + dx.abs() * if dx > F::zero() { tau } else { F::one() - tau };
+            m += 1;
+        }
+    }
+
+    let slopes = &mut slopes[..m];
+    slopes.sort_unstable_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+
+    let mut cumulative = F::zero();
+    let mut slope = slopes[m - 1].0;
+    for &(s, w) in slopes.iter() {
+        cumulative = cumulative + w;
+        if cumulative >= target {
+            slope = s;
+            break;
+        }
+    }
+
+    (slope, cost(point, n, tau, pivot, slope))
+}
+
+/// Fits the quantile `tau` regression line to the `n` points returned by
+/// `point`, the least absolute deviations line for `tau = 0.5`
+///
+/// Some optimal line passes through two of the points. Starting from the
+/// point closest to the mean x value, the best line through the current
+/// pivot is found, and the search moves on to another point on that line
+/// for as long as the sum of check losses decreases. Checking every
+/// point on the line, rather than only the one that set the slope, keeps the
+/// search from stopping early when more than two points are collinear.
+pub(crate) fn fit_quantile<G, F>(
+    point: G,
+    n: usize,
+    tau: F,
+    scratch: &mut [(F, F)],
+) -> Result<(F, F), LinRegError>
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+    if scratch.len() < n {
+        return Err(LinRegError::InvalidParameter);
+    }
+
+    let x0 = point(0).0;
+    let mut x_varies = false;
+    let mut x_sum = F::zero();
+    for i in 0..n {
+        let (x, y) = point(i);
+        if !x.is_finite() || !y.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+        x_varies = x_varies || x != x0;
+        x_sum = x_sum + x;
+    }
+    if !x_varies {
+        return Err(LinRegError::ConstantX);
+    }
+
+    let x_mean = x_sum / F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+    let mut pivot = 0;
+    for i in 1..n {
+        if (point(i).0 - x_mean).abs() < (point(pivot).0 - x_mean).abs() {
+            pivot = i;
+        }
+    }
+
+    let (mut slope, mut best) = best_through(&point, n, tau, pivot, scratch);
+    loop {
+        let (xk, yk) = point(pivot);
+        let mut improved = false;
+
+        for i in 0..n {
+            let (x, y) = point(i);
+            if x == xk || (y - yk) / (x - xk) != slope {
+                continue;
+            }
+
+            let (s, c) = best_through(&point, n, tau, i, scratch);
+            if c < best {
+                pivot = i;
+                slope = s;
+                best = c;
+                improved = true;
+                break;
+            }
+        }
+
+        if !improved {
+            break;
+        }
+    }
+
+    let (xk, yk) = point(pivot);
+    let intercept = yk - slope * xk;
+    if !slope.is_finite() || !intercept.is_finite() {
+        return Err(LinRegError::NonFinite);
+    }
+
+    Ok((slope, intercept))
+}
+
+/// Least absolute deviations regression from two slices
+///
+/// Finds the line that minimizes the sum of the absolute residuals rather
+/// than of their squares: the conditional median line instead of the
+/// conditional mean one of [`linear_regression`](fn.linear_regression.html).
+/// It is the maximum likelihood line for Laplace distributed errors and much
+/// less affected by heavy tails.
+///
+/// The fit is exact: some optimal line passes through two of the points, and
+/// it is found by descending from one such line to the next, each step
+/// solving a weighted median (Wesolowsky's algorithm). Every step takes
+/// O(n log n) time and few steps are usually needed. `scratch` must hold at
+/// least `xs.len()` pairs; its contents are overwritten, and nothing is
+/// allocated.
+///
+/// The optimal line is not always unique. Ties are broken deterministically,
+/// so the same points in the same order always give the same line.
+///
+/// ```rust
+/// use linreg::lad_regression;
+///
+/// let xs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+/// let ys = [3.0, 5.0, 7.0, 40.0, 11.0, 13.0, -20.0];
+///
+/// let mut scratch = [(0.0, 0.0); 7];
+/// let (slope, intercept): (f64, f64) = lad_regression(&xs, &ys, &mut scratch).unwrap();
+/// assert!((slope - 2.0).abs() < 1e-12);
+/// assert!((intercept - 1.0).abs() < 1e-12);
+/// ```
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * `scratch` is too short (`InvalidParameter`)
+/// * all x values are identical (`ConstantX`)
+/// * a value, the slope or the intercept is NaN or infinite (`NonFinite`)
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn lad_regression<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    scratch: &mut [(F, F)],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_quantile(
+        |i| (xs[i].clone().into(), ys[i].clone().into()),
+        xs.len(),
+        constant(0.5),
+        scratch,
+    )
+}
+
+/// Least absolute deviations regression from tuples
+///
+/// The tuple counterpart of [`lad_regression`](fn.lad_regression.html).
+pub fn lad_regression_of<X, Y, F>(
+    xys: &[(X, Y)],
+    scratch: &mut [(F, F)],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_quantile(
+        |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+        xys.len(),
+        constant(0.5),
+        scratch,
+    )
+}
+
+#[cfg(test)]
+fn lad_cost(xys: &[(f64, f64)], (slope, intercept): (f64, f64)) -> f64 {
+    xys.iter()
+        .map(|&(x, y)| (y - intercept - slope * x).abs())
+        .sum()
+}
+
+#[test]
+fn test_lad_matches_best_pair() {
+    // the optimum passes through two points, so compare to all pairs;
+    // rounded values make many points collinear
+    let mut rng = SplitMix64::new(7);
+    for round in 0..40 {
+        let mut xys = [(0.0, 0.0); 25];
+        for (i, p) in xys.iter_mut().enumerate() {
+            let noise = (uniform(&mut rng) - 0.5) * 4.0;
+            let x = (i % 9) as f64 + 0.1 * i as f64;
+            let y = 2.0 - 0.7 * x + noise * noise * noise;
+            *p = if round % 2 == 0 {
+                (x, y)
+            } else {
+                (x.round(), y.round())
+            };
+        }
+
+        let mut best = f64::INFINITY;
+        for &(xi, yi) in xys.iter() {
+            for &(xj, yj) in xys.iter() {
+                if xi != xj {
+                    let slope = (yj - yi) / (xj - xi);
+                    best = best.min(lad_cost(&xys, (slope, yi - slope * xi)));
+                }
+            }
+        }
+
+        let mut scratch = [(0.0, 0.0); 25];
+        let line = lad_regression_of(&xys, &mut scratch).unwrap();
+        assert!((lad_cost(&xys, line) - best).abs() < 1e-9 * best);
+    }
+}
+
+#[test]
+fn test_lad_collinear() {
+    // most points on one line, with several collinear with the start
+    let xys: [(i16, i16); 9] = [
+        (0, 1),
+        (1, 4),
+        (2, 7),
+        (3, 10),
+        (4, -30),
+        (5, 16),
+        (6, 19),
+        (7, 60),
+        (8, 25),
+    ];
+
+    let mut scratch = [(0.0, 0.0); 9];
+    let (slope, intercept): (f64, f64) = lad_regression_of(&xys, &mut scratch).unwrap();
+    assert_eq!(3.0, slope);
+    assert_eq!(1.0, intercept);
+}
+
+#[test]
+fn test_lad_ties() {
+    // every line between the two pairs of points is optimal
+    let xs = [0.0, 0.0, 1.0, 1.0];
+    let ys = [0.0, 2.0, 0.0, 2.0];
+
+    let mut scratch = [(0.0, 0.0); 4];
+    let first: (f64, f64) = lad_regression(&xs, &ys, &mut scratch).unwrap();
+    for _ in 0..3 {
+        assert_eq!(Ok(first), lad_regression(&xs, &ys, &mut scratch));
+    }
+    assert_eq!(
+        4.0,
+        lad_cost(&[(0.0, 0.0), (0.0, 2.0), (1.0, 0.0), (1.0, 2.0)], first)
+    );
+}
+
+#[test]
+fn test_lad_errors() {
+    let mut scratch = [(0.0, 0.0); 2];
+
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        lad_regression(&[1.0, 2.0], &[1.0], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        lad_regression_of::<f64, f64, f64>(&[], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        lad_regression(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        lad_regression(&[2.0, 2.0], &[1.0, 3.0], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::NonFinite),
+        lad_regression(&[1.0, 2.0], &[1.0, f64::NAN], &mut scratch)
+    );
+}
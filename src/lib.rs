@@ -28,16 +28,88 @@
 //!
 //!    assert_eq!(Some((0.6, 2.2)), linear_regression(&xs, &ys));
 //! ```
+//!
+//! Each function also has a `try_` variant returning a `Result` whose
+//! [`LinRegError`](enum.LinRegError.html) tells why no regression line could be calculated.
+//! The `_stats` variants return a [`RegressionResult`](struct.RegressionResult.html)
+//! that includes goodness-of-fit statistics like R².
 #![no_std]
 
 extern crate num_traits;
 
 use num_traits::Float;
 
-#[cfg(test)]
-#[macro_use]
+#[cfg(any(test, feature = "std"))]
+#[cfg_attr(test, macro_use)]
 extern crate std;
 
+#[cfg(feature = "alloc")]
+#[cfg_attr(not(test), macro_use)]
+extern crate alloc;
+
+#[cfg(feature = "rayon")]
+extern crate rayon;
+
+mod constrained;
+mod dist;
+#[cfg(feature = "alloc")]
+mod elastic_net;
+mod error;
+mod exponential;
+mod lad;
+mod lts;
+mod m_estimator;
+mod multi;
+mod online;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod polynomial;
+mod quantile;
+mod ransac;
+mod residuals;
+mod result;
+mod ridge;
+mod siegel;
+mod stats;
+mod theil_sen;
+mod weighted;
+mod window;
+
+pub use constrained::{
+    linear_regression_through_origin, linear_regression_through_origin_of,
+    linear_regression_through_point, linear_regression_through_point_of,
+    linear_regression_with_intercept, linear_regression_with_intercept_of,
+    linear_regression_with_slope, linear_regression_with_slope_of,
+};
+#[cfg(feature = "alloc")]
+pub use elastic_net::{ElasticNet, ElasticNetFit};
+pub use error::LinRegError;
+pub use exponential::ExponentialRegression;
+pub use lad::{lad_regression, lad_regression_of};
+pub use lts::{Lts, LtsResult};
+pub use m_estimator::{Loss, MEstimator, MEstimatorResult};
+pub use multi::{multiple_linear_regression, MultiRegression, MultiRegressionResult};
+#[cfg(feature = "alloc")]
+pub use multi::{DynMultiRegression, DynMultiRegressionResult};
+pub use online::OnlineRegression;
+#[cfg(feature = "rayon")]
+pub use parallel::{par_linear_regression, par_linear_regression_of};
+pub use polynomial::{polynomial_regression, polynomial_regression_of, PolynomialRegressionResult};
+pub use quantile::{quantile_regression, quantile_regression_of, QuantileRegression};
+pub use ransac::{RandomSource, Ransac, RansacResult, SplitMix64};
+pub use residuals::ResidualSummary;
+pub use result::{ConfidenceIntervals, RegressionResult};
+pub use ridge::{
+    ridge_lin_reg, ridge_linear_regression, ridge_linear_regression_gcv,
+    ridge_linear_regression_gcv_of, ridge_linear_regression_of, RidgeLine, RidgeRegressionResult,
+};
+pub use siegel::{siegel_regression, siegel_regression_of};
+pub use theil_sen::TheilSen;
+#[cfg(feature = "alloc")]
+pub use theil_sen::{theil_sen_regression, theil_sen_regression_of};
+pub use weighted::{weighted_lin_reg, weighted_linear_regression, weighted_linear_regression_of};
+pub use window::SlidingWindowRegression;
+
 #[cfg(test)]
 use std::vec::Vec;
 
@@ -54,7 +126,37 @@ use core::iter::Sum;
 /// Since there is a mean, this function assumes that `xs` and `ys` are both non-empty.
 ///
 /// Returns `Some(slope, intercept)` of the regression line.
-pub fn lin_reg<'a, I, F>(xys: I, x_mean: F, y_mean: F) -> Option<(F, F)>
+pub fn lin_reg<I, F>(xys: I, x_mean: F, y_mean: F) -> Option<(F, F)>
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    try_lin_reg(xys, x_mean, y_mean).ok()
+}
+
+/// Calculates a linear regression, reporting why it failed
+///
+/// Like [`lin_reg`](fn.lin_reg.html), but returns an error if
+///
+/// * `xys` is empty (`EmptyInput`)
+/// * all x values are identical (`ConstantX`)
+/// * the slope or intercept is NaN or infinite (`NonFinite`)
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn try_lin_reg<I, F>(xys: I, x_mean: F, y_mean: F) -> Result<(F, F), LinRegError>
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    lin_reg_stats(xys, x_mean, y_mean).map(|r| (r.slope, r.intercept))
+}
+
+/// Calculates a linear regression with goodness-of-fit statistics
+///
+/// Like [`try_lin_reg`](fn.try_lin_reg.html), but returns the full
+/// [`RegressionResult`](struct.RegressionResult.html). All statistics are
+/// gathered in the same single pass over `xys`.
+pub fn lin_reg_stats<I, F>(xys: I, x_mean: F, y_mean: F) -> Result<RegressionResult<F>, LinRegError>
 where
     I: Iterator<Item = (F, F)>,
     F: Float,
@@ -62,24 +164,39 @@ where
     // SUM (x-mean(x))^2
     let mut xxm2 = F::zero();
 
+    // SUM (y-mean(y))^2
+    let mut yym2 = F::zero();
+
     // SUM (x-mean(x)) (y-mean(y))
     let mut xmym2 = F::zero();
 
+    let mut n = 0;
+
+    // the first x value, used to detect a vertical line
+    let mut first_x = None;
+    let mut x_varies = false;
+
     for (x, y) in xys {
+        match first_x {
+            None => first_x = Some(x),
+            Some(x0) => x_varies = x_varies || x != x0,
+        }
+
+        n += 1;
         xxm2 = xxm2 + (x - x_mean) * (x - x_mean);
+        yym2 = yym2 + (y - y_mean) * (y - y_mean);
         xmym2 = xmym2 + (x - x_mean) * (y - y_mean);
     }
 
-    let slope = xmym2 / xxm2;
-
-    // we check for divide-by-zero after the fact
-    if slope.is_nan() {
-        return None;
+    if first_x.is_none() {
+        return Err(LinRegError::EmptyInput);
     }
 
-    let intercept = y_mean - slope * x_mean;
+    if !x_varies {
+        return Err(LinRegError::ConstantX);
+    }
 
-    Some((slope, intercept))
+    RegressionResult::from_moments(n, x_mean, y_mean, xxm2, yym2, xmym2)
 }
 
 /// Linear regression from two slices
@@ -95,23 +212,57 @@ where
 ///
 /// Returns `Some(slope, intercept)` of the regression line.
 pub fn linear_regression<X, Y, F>(xs: &[X], ys: &[Y]) -> Option<(F, F)>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float + Sum,
+{
+    try_linear_regression(xs, ys).ok()
+}
+
+/// Linear regression from two slices, reporting why it failed
+///
+/// Like [`linear_regression`](fn.linear_regression.html), but returns a
+/// [`LinRegError`](enum.LinRegError.html) instead of `None`.
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn try_linear_regression<X, Y, F>(xs: &[X], ys: &[Y]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float + Sum,
+{
+    linear_regression_stats(xs, ys).map(|r| (r.slope, r.intercept))
+}
+
+/// Linear regression with goodness-of-fit statistics from two slices
+///
+/// Like [`try_linear_regression`](fn.try_linear_regression.html), but returns
+/// the full [`RegressionResult`](struct.RegressionResult.html).
+pub fn linear_regression_stats<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+) -> Result<RegressionResult<F>, LinRegError>
 where
     X: Clone + Into<F>,
     Y: Clone + Into<F>,
     F: Float + Sum,
 {
     if xs.len() != ys.len() {
-        return None;
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    if xs.is_empty() {
+        return Err(LinRegError::EmptyInput);
     }
 
-    // if one of the axes is empty, we return `None`
     let x_sum: F = xs.iter().cloned().map(|i| i.into()).sum();
-    let n = F::from(xs.len())?;
+    let n = F::from(xs.len()).ok_or(LinRegError::CountNotRepresentable)?;
     let x_mean = x_sum / n;
     let y_sum: F = ys.iter().cloned().map(|i| i.into()).sum();
     let y_mean = y_sum / n;
 
-    lin_reg(
+    lin_reg_stats(
         xs.iter()
             .map(|i| i.clone().into())
             .zip(ys.iter().map(|i| i.clone().into())),
@@ -132,17 +283,47 @@ where
 ///
 /// Returns `Some(slope, intercept)` of the regression line.
 pub fn linear_regression_of<X, Y, F>(xys: &[(X, Y)]) -> Option<(F, F)>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    try_linear_regression_of(xys).ok()
+}
+
+/// Linear regression from tuples, reporting why it failed
+///
+/// Like [`linear_regression_of`](fn.linear_regression_of.html), but returns a
+/// [`LinRegError`](enum.LinRegError.html) instead of `None`.
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn try_linear_regression_of<X, Y, F>(xys: &[(X, Y)]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    linear_regression_stats_of(xys).map(|r| (r.slope, r.intercept))
+}
+
+/// Linear regression with goodness-of-fit statistics from tuples
+///
+/// Like [`try_linear_regression_of`](fn.try_linear_regression_of.html), but
+/// returns the full [`RegressionResult`](struct.RegressionResult.html).
+pub fn linear_regression_stats_of<X, Y, F>(
+    xys: &[(X, Y)],
+) -> Result<RegressionResult<F>, LinRegError>
 where
     X: Clone + Into<F>,
     Y: Clone + Into<F>,
     F: Float,
 {
     if xys.is_empty() {
-        return None;
+        return Err(LinRegError::EmptyInput);
     }
     // We're handrolling the mean computation here, because our generic implementation can't handle tuples.
     // If we ran the generic impl on each tuple field, that would be very cache inefficient
-    let n = F::from(xys.len())?;
+    let n = F::from(xys.len()).ok_or(LinRegError::CountNotRepresentable)?;
     let (x_sum, y_sum) = xys
         .iter()
         .cloned()
@@ -152,7 +333,7 @@ where
     let x_mean = x_sum / n;
     let y_mean = y_sum / n;
 
-    lin_reg(
+    lin_reg_stats(
         xys.iter()
             .map(|(x, y)| (x.clone().into(), y.clone().into())),
         x_mean,
@@ -182,3 +363,47 @@ fn test_integer_regression() {
 
     assert_eq!(Some((0.6, 2.2)), linear_regression(&xs, &ys));
 }
+
+#[test]
+fn test_errors() {
+    let xs: Vec<f64> = vec![1.0, 2.0, 3.0];
+    let ys: Vec<f64> = vec![2.0, 4.0];
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        try_linear_regression::<_, _, f64>(&xs, &ys)
+    );
+
+    let empty: Vec<f64> = vec![];
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        try_linear_regression::<_, _, f64>(&empty, &empty)
+    );
+
+    let tuples: Vec<(f64, f64)> = vec![(3.0, 1.0), (3.0, 2.0), (3.0, 5.0)];
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        try_linear_regression_of::<_, _, f64>(&tuples)
+    );
+    assert_eq!(None, linear_regression_of::<_, _, f64>(&tuples));
+
+    let tuples: Vec<(f64, f64)> = vec![(1.0, 1.0), (2.0, f64::NAN)];
+    assert_eq!(
+        Err(LinRegError::NonFinite),
+        try_linear_regression_of::<_, _, f64>(&tuples)
+    );
+}
+
+#[test]
+fn test_regression_stats() {
+    let xs: Vec<u8> = vec![1, 2, 3, 4, 5];
+    let ys: Vec<u8> = vec![2, 4, 5, 4, 5];
+
+    let r: RegressionResult<f64> = linear_regression_stats(&xs, &ys).unwrap();
+    assert_eq!((0.6, 2.2), (r.slope, r.intercept));
+    assert_eq!(5, r.n);
+    assert_eq!((10.0, 6.0, 6.0), (r.sxx, r.syy, r.sxy));
+    assert!((r.r_squared - 0.6).abs() < 1e-12);
+
+    let tuples: Vec<(u8, u8)> = xs.iter().cloned().zip(ys.iter().cloned()).collect();
+    assert_eq!(Ok(r), linear_regression_stats_of(&tuples));
+}
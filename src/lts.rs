@@ -0,0 +1,468 @@
+use num_traits::Float;
+
+use core::cmp::Ordering;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+#[cfg(test)]
+use ransac::SplitMix64;
+use ransac::{random_index, RandomSource};
+use try_lin_reg;
+
+/// Number of candidates from the random starts that are concentrated until
+/// convergence
+const CANDIDATES: usize = 10;
+
+/// Least trimmed squares regression, a high-breakdown line fitter
+///
+/// Finds the line whose `h` smallest squared residuals have the smallest
+/// sum, for a coverage `h` between half and all of the points. Up to `n - h`
+/// points can be arbitrarily wrong, so with the default coverage of
+/// `(n + 3) / 2` almost half of them, and the line is the least squares
+/// line of the `h` points it keeps.
+///
+/// Uses the FAST-LTS algorithm of Rousseeuw and Van Driessen: the line
+/// through two random points is improved by concentration steps, each
+/// fitting [`try_lin_reg`](fn.try_lin_reg.html) to the `h` points closest
+/// to the current line, which never increases the trimmed sum. After two steps from
+/// every start, the ten best lines are concentrated until they settle.
+///
+/// ```rust
+/// use linreg::{Lts, SplitMix64};
+///
+/// let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
+/// let ys = [1.0, 3.1, 4.9, 7.0, 9.1, 10.9, 40.0, 41.0, 42.0, 43.0];
+///
+/// let mut subset = [false; 10];
+/// let mut scratch = [0.0; 10];
+/// let fit = Lts::new()
+///     .fit(&xs, &ys, &mut SplitMix64::new(1), &mut subset, &mut scratch)
+///     .unwrap();
+///
+/// assert!((fit.slope - 2.0f64).abs() < 0.05);
+/// assert_eq!([true, true, true, true, true, true, false, false, false, false], subset);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Lts {
+    coverage: Option<usize>,
+    starts: usize,
+    max_iterations: usize,
+}
+
+/// Line found by [`Lts`](struct.Lts.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LtsResult<F> {
+    /// Slope of the least squares line through the selected subset
+    pub slope: F,
+    /// Intercept of the least squares line through the selected subset
+    pub intercept: F,
+    /// Number of points in the selected subset
+    pub coverage: usize,
+    /// Sum of the squared residuals of the selected subset
+    pub rss: F,
+}
+
+impl Default for Lts {
+    fn default() -> Self {
+        Lts::new()
+    }
+}
+
+impl Lts {
+    /// Creates a fitter with the default coverage of `(n + 3) / 2` points,
+    /// 500 random starts and at most 100 concentration steps per candidate
+    pub fn new() -> Self {
+        Lts {
+            coverage: None,
+            starts: 500,
+            max_iterations: 100,
+        }
+    }
+
+    /// Sets the number `h` of points the line is fitted to
+    ///
+    /// A smaller coverage tolerates more outliers, a larger one is more
+    /// efficient on clean data. Values below 2 or above the number of points
+    /// make [`fit`](#method.fit) fail with `InvalidParameter`.
+    pub fn with_coverage(mut self, coverage: usize) -> Self {
+        self.coverage = Some(coverage);
+        self
+    }
+
+    /// Sets the number of random starts
+    pub fn with_starts(mut self, starts: usize) -> Self {
+        self.starts = starts;
+        self
+    }
+
+    /// Sets the maximum number of concentration steps for each of the best
+    /// candidates
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Fits a line to the points in two slices
+    ///
+    /// Sets `subset[i]` to whether point `i` is among the `h` points the line
+    /// is fitted to; `subset` must hold at least `xs.len()` values.
+    /// `scratch` must hold at least `xs.len()` values; its contents are
+    /// overwritten, and nothing is allocated. Returns an error if
+    ///
+    /// * `xs` and `ys` differ in length (`LengthMismatch`)
+    /// * `xs` and `ys` are empty (`EmptyInput`)
+    /// * there is only one point (`TooFewPoints`)
+    /// * `subset` or `scratch` is too short, the coverage is out of range or
+    ///   there are no starts (`InvalidParameter`)
+    /// * no start had two different x values (`ConstantX`)
+    /// * a value or the line is NaN or infinite (`NonFinite`)
+    pub fn fit<X, Y, F, R>(
+        &self,
+        xs: &[X],
+        ys: &[Y],
+        rng: &mut R,
+        subset: &mut [bool],
+        scratch: &mut [F],
+    ) -> Result<LtsResult<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+        F: Float,
+        R: RandomSource,
+    {
+        if xs.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        self.fit_points(
+            |i| (xs[i].clone().into(), ys[i].clone().into()),
+            xs.len(),
+            rng,
+            subset,
+            scratch,
+        )
+    }
+
+    /// Fits a line to a slice of tuples
+    ///
+    /// The tuple counterpart of [`fit`](#method.fit).
+    pub fn fit_of<X, Y, F, R>(
+        &self,
+        xys: &[(X, Y)],
+        rng: &mut R,
+        subset: &mut [bool],
+        scratch: &mut [F],
+    ) -> Result<LtsResult<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+        F: Float,
+        R: RandomSource,
+    {
+        self.fit_points(
+            |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+            xys.len(),
+            rng,
+            subset,
+            scratch,
+        )
+    }
+
+    fn fit_points<G, F, R>(
+        &self,
+        point: G,
+        n: usize,
+        rng: &mut R,
+        subset: &mut [bool],
+        scratch: &mut [F],
+    ) -> Result<LtsResult<F>, LinRegError>
+    where
+        G: Fn(usize) -> (F, F),
+        F: Float,
+        R: RandomSource,
+    {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+        if n == 1 {
+            return Err(LinRegError::TooFewPoints);
+        }
+        let h = match self.coverage {
+            Some(h) => h,
+            None => ((n + 3) / 2).min(n),
+        };
+        if subset.len() < n || scratch.len() < n || h < 2 || h > n || self.starts == 0 {
+            return Err(LinRegError::InvalidParameter);
+        }
+        for i in 0..n {
+            let (x, y) = point(i);
+            if !x.is_finite() || !y.is_finite() {
+                return Err(LinRegError::NonFinite);
+            }
+        }
+
+        let subset = &mut subset[..n];
+        let residuals = &mut scratch[..n];
+
+        // the best candidates as (trimmed sum, slope, intercept), best first
+        let mut candidates: [Option<(F, F, F)>; CANDIDATES] = [None; CANDIDATES];
+        for _ in 0..self.starts {
+            let i = random_index(rng, n);
+            let j = (i + 1 + random_index(rng, n - 1)) % n;
+            let (x_i, y_i) = point(i);
+            let (x_j, y_j) = point(j);
+            if x_i == x_j {
+                continue;
+            }
+            let slope = (y_j - y_i) / (x_j - x_i);
+            let line = (slope, y_i - slope * x_i);
+
+            let candidate = concentrate(&point, h, line, 2, residuals, subset);
+            if candidates.contains(&Some(candidate)) {
+                continue;
+            }
+            // insert into the sorted candidates, dropping the worst
+            let mut k = CANDIDATES;
+            while k > 0 && candidates[k - 1].map_or(true, |c| candidate.0 < c.0) {
+                k -= 1;
+            }
+            if k < CANDIDATES {
+                candidates[k..].rotate_right(1);
+                candidates[k] = Some(candidate);
+            }
+        }
+
+        let mut best: Option<(F, F, F)> = None;
+        for &(_, slope, intercept) in candidates.iter().flatten() {
+            let candidate = concentrate(
+                &point,
+                h,
+                (slope, intercept),
+                self.max_iterations,
+                residuals,
+                subset,
+            );
+            if best.map_or(true, |b| candidate.0 < b.0) {
+                best = Some(candidate);
+            }
+        }
+        let (_, slope, intercept) = best.ok_or(LinRegError::ConstantX)?;
+
+        // the least squares line of the final subset
+        select(&point, h, (slope, intercept), residuals, subset);
+        let (slope, intercept) = fit_subset(&point, h, subset)?;
+        let rss = (0..n).filter(|&i| subset[i]).fold(F::zero(), |sum, i| {
+            let (x, y) = point(i);
+            let r = y - (intercept + slope * x);
+            sum + r * r
+        });
+
+        Ok(LtsResult {
+            slope,
+            intercept,
+            coverage: h,
+            rss,
+        })
+    }
+}
+
+/// Marks the `h` points with the smallest squared residuals from `line` in
+/// `subset`, taking tied points in index order
+///
+/// Returns the sum of their squared residuals.
+fn select<G, F>(
+    point: &G,
+    h: usize,
+    (slope, intercept): (F, F),
+    residuals: &mut [F],
+    subset: &mut [bool],
+) -> F
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    let squared = |i: usize| {
+        let (x, y) = point(i);
+        let r = y - (intercept + slope * x);
+        r * r
+    };
+
+    for (i, r) in residuals.iter_mut().enumerate() {
+        *r = squared(i);
+    }
+    let compare = |a: &F, b: &F| a.partial_cmp(b).unwrap_or(Ordering::Equal);
+    let (lower, &mut cutoff, _) = residuals.select_nth_unstable_by(h - 1, compare);
+    let mut ties = h - lower.iter().filter(|&&r| r < cutoff).count();
+
+    let mut sum = F::zero();
+    for (i, selected) in subset.iter_mut().enumerate() {
+        let r = squared(i);
+        *selected = r < cutoff || (r == cutoff && ties > 0);
+        if r == cutoff && ties > 0 {
+            ties -= 1;
+        }
+        if *selected {
+            sum = sum + r;
+        }
+    }
+
+    sum
+}
+
+/// Least squares line of the `h` points marked in `subset`
+fn fit_subset<G, F>(point: &G, h: usize, subset: &[bool]) -> Result<(F, F), LinRegError>
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    let selected = || (0..subset.len()).filter(|&i| subset[i]).map(point);
+    let (x_sum, y_sum) =
+        selected().fold((F::zero(), F::zero()), |(sx, sy), (x, y)| (sx + x, sy + y));
+    let h = F::from(h).ok_or(LinRegError::CountNotRepresentable)?;
+    try_lin_reg(selected(), x_sum / h, y_sum / h)
+}
+
+/// Applies up to `steps` concentration steps to `line`
+///
+/// Each step fits the least squares line to the `h` points closest to the
+/// current one, and the steps stop once the trimmed sum no longer decreases.
+/// Returns `(trimmed sum, slope, intercept)`.
+fn concentrate<G, F>(
+    point: &G,
+    h: usize,
+    mut line: (F, F),
+    steps: usize,
+    residuals: &mut [F],
+    subset: &mut [bool],
+) -> (F, F, F)
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    let mut trimmed = select(point, h, line, residuals, subset);
+    for _ in 0..steps {
+        let next = match fit_subset(point, h, subset) {
+            Ok(next) => next,
+            Err(_) => break,
+        };
+        let next_trimmed = select(point, h, next, residuals, subset);
+        if next_trimmed >= trimmed {
+            break;
+        }
+        line = next;
+        trimmed = next_trimmed;
+    }
+
+    (trimmed, line.0, line.1)
+}
+
+#[test]
+fn test_lts_contaminated_line() {
+    // 60 points on y = 2 x + 1 with small noise, and a tight cluster of 40
+    // outliers that drags the least squares line far away
+    let mut xys = [(0.0f64, 0.0f64); 100];
+    for (i, p) in xys.iter_mut().enumerate() {
+        *p = if i < 60 {
+            let x = i as f64 / 6.0;
+            let noise = ((i * 7) % 11) as f64 * 0.02 - 0.1;
+            (x, 2.0 * x + 1.0 + noise)
+        } else {
+            (8.0 + (i % 4) as f64 * 0.1, -30.0 + (i % 5) as f64 * 0.1)
+        };
+    }
+    let (ols_slope, _): (f64, f64) = ::linear_regression_of(&xys).unwrap();
+    assert!(ols_slope < 0.0);
+
+    let mut subset = [false; 100];
+    let mut scratch = [0.0; 100];
+    let fit = Lts::new()
+        .fit_of(&xys, &mut SplitMix64::new(3), &mut subset, &mut scratch)
+        .unwrap();
+
+    assert_eq!(51, fit.coverage);
+    assert!((fit.slope - 2.0).abs() < 0.02);
+    assert!((fit.intercept - 1.0).abs() < 0.1);
+    assert_eq!(51, subset.iter().filter(|&&s| s).count());
+    assert!(subset[60..].iter().all(|&s| !s));
+
+    // the line is the least squares line of the subset, with its rss
+    let kept: Vec<(f64, f64)> = (0..100).filter(|&i| subset[i]).map(|i| xys[i]).collect();
+    let (slope, intercept): (f64, f64) = ::linear_regression_of(&kept).unwrap();
+    assert!((slope - fit.slope).abs() < 1e-12);
+    assert!((intercept - fit.intercept).abs() < 1e-12);
+    let rss: f64 = kept
+        .iter()
+        .map(|&(x, y)| (y - intercept - slope * x).powi(2))
+        .sum();
+    assert!((rss - fit.rss).abs() < 1e-12);
+
+    // full coverage is least squares
+    let fit = Lts::new()
+        .with_coverage(100)
+        .fit_of(&xys, &mut SplitMix64::new(3), &mut subset, &mut scratch)
+        .unwrap();
+    assert!((fit.slope - ols_slope).abs() < 1e-12);
+}
+
+#[test]
+fn test_lts_errors() {
+    let mut subset = [false; 3];
+    let mut scratch = [0.0; 3];
+    let mut rng = SplitMix64::new(0);
+    let lts = Lts::new();
+
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        lts.fit(&[1.0, 2.0], &[1.0], &mut rng, &mut subset, &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        lts.fit_of::<f64, f64, f64, _>(&[], &mut rng, &mut subset, &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::TooFewPoints),
+        lts.fit(&[1.0], &[1.0], &mut rng, &mut subset, &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        lts.with_coverage(4).fit(
+            &[1.0, 2.0, 3.0],
+            &[1.0, 2.0, 3.0],
+            &mut rng,
+            &mut subset,
+            &mut scratch
+        )
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        lts.fit(
+            &[1.0, 2.0, 3.0],
+            &[1.0, 2.0, 3.0],
+            &mut rng,
+            &mut subset[..2],
+            &mut scratch
+        )
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        lts.fit(
+            &[2.0, 2.0, 2.0],
+            &[1.0, 2.0, 3.0],
+            &mut rng,
+            &mut subset,
+            &mut scratch
+        )
+    );
+    assert_eq!(
+        Err(LinRegError::NonFinite),
+        lts.fit(
+            &[1.0, 2.0, 3.0],
+            &[1.0, f64::NAN, 3.0],
+            &mut rng,
+            &mut subset,
+            &mut scratch
+        )
+    );
+}
@@ -0,0 +1,411 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use dist::constant;
+use error::LinRegError;
+use stats::median;
+use try_lin_reg;
+use weighted::{weighted_lin_reg, weighted_means};
+
+/// Loss function of an M-estimator, with its tuning constant
+///
+/// The tuning constant is measured in units of the residual scale. The
+/// defaults of [`huber`](#method.huber) and [`tukey`](#method.tukey) give 95%
+/// of the efficiency of least squares for normally distributed errors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Loss<F> {
+    /// Huber loss: quadratic for small residuals and linear beyond the
+    /// constant, so outliers keep a small influence
+    Huber(F),
+    /// Tukey's bisquare loss: residuals beyond the constant have no
+    /// influence at all
+    Tukey(F),
+}
+
+impl<F: Float> Loss<F> {
+    /// Huber loss with the tuning constant 1.345
+    pub fn huber() -> Self {
+        Loss::Huber(constant(1.345))
+    }
+
+    /// Tukey bisquare loss with the tuning constant 4.685
+    pub fn tukey() -> Self {
+        Loss::Tukey(constant(4.685))
+    }
+
+    /// Weight of a residual of `u` times the scale
+    pub fn weight(&self, u: F) -> F {
+        match *self {
+            Loss::Huber(k) => {
+                if u.abs() <= k {
+                    F::one()
+                } else {
+                    k / u.abs()
+                }
+            }
+            Loss::Tukey(c) => {
+                if u.abs() < c {
+                    let v = F::one() - (u / c) * (u / c);
+                    v * v
+                } else {
+                    F::zero()
+                }
+            }
+        }
+    }
+
+    fn tuning_constant(&self) -> F {
+        match *self {
+            Loss::Huber(k) => k,
+            Loss::Tukey(c) => c,
+        }
+    }
+}
+
+/// Robust regression line by M-estimation
+///
+/// Minimizes the sum of a [`Loss`](enum.Loss.html) of the scaled residuals
+/// with iteratively reweighted least squares: every iteration estimates the
+/// residual scale as the median absolute residual divided by 0.6745, weights
+/// each point by its scaled residual and solves the
+/// [weighted regression](fn.weighted_lin_reg.html). Unlike Theil–Sen or
+/// RANSAC, outliers are downweighted rather than discarded.
+///
+/// The iteration starts from the least squares line. Since the Tukey loss
+/// has several local minima, it is started from the Huber fit instead.
+///
+/// ```rust
+/// use linreg::{Loss, MEstimator};
+///
+/// let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+/// let ys = [1.1, 2.9, 5.0, 7.1, 8.9, 11.0, 40.0, 15.1];
+///
+/// let mut weights = [0.0; 8];
+/// let fit = MEstimator::new(Loss::tukey())
+///     .unwrap()
+///     .fit(&xs, &ys, &mut weights)
+///     .unwrap();
+///
+/// assert!((fit.slope - 2.0f64).abs() < 0.05);
+/// assert_eq!(0.0, weights[6]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MEstimator<F> {
+    loss: Loss<F>,
+    tolerance: F,
+    max_iterations: usize,
+}
+
+/// Line found by an [`MEstimator`](struct.MEstimator.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MEstimatorResult<F> {
+    /// Slope of the regression line
+    pub slope: F,
+    /// Intercept of the regression line
+    pub intercept: F,
+    /// Robust estimate of the standard deviation of the residuals
+    pub scale: F,
+    /// Reweighting iterations, including those of the Huber start for the
+    /// Tukey loss
+    pub iterations: usize,
+}
+
+impl<F: Float> MEstimator<F> {
+    /// Creates an estimator for the given loss function
+    ///
+    /// Returns `InvalidParameter` if the tuning constant is not positive and
+    /// finite.
+    pub fn new(loss: Loss<F>) -> Result<Self, LinRegError> {
+        let c = loss.tuning_constant();
+        if !c.is_finite() || c <= F::zero() {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        Ok(MEstimator {
+            loss,
+            tolerance: constant(1e-10),
+            max_iterations: 100,
+        })
+    }
+
+    /// Sets the convergence tolerance, `1e-10` by default
+    ///
+    /// The iteration stops once the fitted values move by less than this
+    /// share of the size of the residuals.
+    pub fn with_tolerance(mut self, tolerance: F) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Sets the maximum number of reweighting iterations, 100 by default
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Fits the line to the points in two slices
+    ///
+    /// `weights` must hold at least `xs.len()` values and receives the
+    /// weights of the final iteration, which shows the points that were
+    /// treated as outliers. Returns an error if
+    ///
+    /// * `xs` and `ys` differ in length (`LengthMismatch`)
+    /// * `xs` and `ys` are empty (`EmptyInput`)
+    /// * `weights` is too short or the tolerance is not positive
+    ///   (`InvalidParameter`)
+    /// * all x values with a nonzero weight are identical (`ConstantX`)
+    /// * all weights became zero (`ZeroWeights`)
+    /// * a NaN or infinite value occurred (`NonFinite`)
+    /// * the line did not settle within the iteration limit (`NotConverged`)
+    pub fn fit<X, Y>(
+        &self,
+        xs: &[X],
+        ys: &[Y],
+        weights: &mut [F],
+    ) -> Result<MEstimatorResult<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        if xs.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        self.fit_points(
+            |i| (xs[i].clone().into(), ys[i].clone().into()),
+            xs.len(),
+            weights,
+        )
+    }
+
+    /// Fits the line to a slice of tuples
+    ///
+    /// The tuple counterpart of [`fit`](#method.fit).
+    pub fn fit_of<X, Y>(
+        &self,
+        xys: &[(X, Y)],
+        weights: &mut [F],
+    ) -> Result<MEstimatorResult<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        self.fit_points(
+            |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+            xys.len(),
+            weights,
+        )
+    }
+
+    fn fit_points<G>(
+        &self,
+        point: G,
+        n: usize,
+        weights: &mut [F],
+    ) -> Result<MEstimatorResult<F>, LinRegError>
+    where
+        G: Fn(usize) -> (F, F),
+    {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+        if weights.len() < n || self.tolerance.is_nan() || self.tolerance <= F::zero() {
+            return Err(LinRegError::InvalidParameter);
+        }
+        let weights = &mut weights[..n];
+
+        let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+        let (x_sum, y_sum) = (0..n)
+            .map(&point)
+            .fold((F::zero(), F::zero()), |(sx, sy), (x, y)| (sx + x, sy + y));
+        let line = try_lin_reg((0..n).map(&point), x_sum / n_f, y_sum / n_f)?;
+
+        match self.loss {
+            Loss::Huber(_) => self.reweight(&point, weights, line, 0),
+            Loss::Tukey(_) => {
+                let huber = MEstimator {
+                    loss: Loss::huber(),
+                    ..*self
+                };
+                let start = huber.reweight(&point, weights, line, 0)?;
+                self.reweight(
+                    &point,
+                    weights,
+                    (start.slope, start.intercept),
+                    start.iterations,
+                )
+            }
+        }
+    }
+
+    /// Runs the reweighting iterations from the line `(slope, intercept)`
+    fn reweight<G>(
+        &self,
+        point: &G,
+        weights: &mut [F],
+        (mut slope, mut intercept): (F, F),
+        mut iterations: usize,
+    ) -> Result<MEstimatorResult<F>, LinRegError>
+    where
+        G: Fn(usize) -> (F, F),
+    {
+        let residual = |slope: F, intercept: F, i: usize| {
+            let (x, y) = point(i);
+            y - (intercept + slope * x)
+        };
+
+        for _ in 0..self.max_iterations {
+            for (i, w) in weights.iter_mut().enumerate() {
+                *w = residual(slope, intercept, i).abs();
+            }
+            let scale = median(weights) / constant(0.674_5);
+
+            if !scale.is_finite() {
+                return Err(LinRegError::NonFinite);
+            }
+            if scale == F::zero() {
+                // more than half of the points lie exactly on the line
+                for (i, w) in weights.iter_mut().enumerate() {
+                    let on_line = residual(slope, intercept, i) == F::zero();
+                    *w = if on_line { F::one() } else { F::zero() };
+                }
+                return Ok(MEstimatorResult {
+                    slope,
+                    intercept,
+                    scale,
+                    iterations,
+                });
+            }
+
+            for (i, w) in weights.iter_mut().enumerate() {
+                *w = self.loss.weight(residual(slope, intercept, i) / scale);
+            }
+
+            iterations += 1;
+            let xyws = || {
+                weights.iter().enumerate().map(|(i, &w)| {
+                    let (x, y) = point(i);
+                    (x, y, w)
+                })
+            };
+            let (x_mean, y_mean) = weighted_means(xyws())?;
+            let (new_slope, new_intercept) = weighted_lin_reg(xyws(), x_mean, y_mean)?;
+
+            // movement of the fitted values compared to the residuals
+            let mut change = F::zero();
+            let mut size = F::zero();
+            for i in 0..weights.len() {
+                let d = residual(slope, intercept, i) - residual(new_slope, new_intercept, i);
+                let r = residual(new_slope, new_intercept, i);
+                change = change + d * d;
+                size = size + r * r;
+            }
+
+            slope = new_slope;
+            intercept = new_intercept;
+
+            if change <= self.tolerance * self.tolerance * size {
+                return Ok(MEstimatorResult {
+                    slope,
+                    intercept,
+                    scale,
+                    iterations,
+                });
+            }
+        }
+
+        Err(LinRegError::NotConverged)
+    }
+}
+
+#[cfg(test)]
+fn contaminated_line() -> ([f64; 20], [f64; 20]) {
+    let mut xs = [0.0; 20];
+    let mut ys = [0.0; 20];
+    for (i, (x, y)) in xs.iter_mut().zip(ys.iter_mut()).enumerate() {
+        *x = i as f64;
+        *y = 1.0 + 0.5 * *x + ((i * 7 % 5) as f64 - 2.0) * 0.1;
+    }
+    ys[15] += 30.0;
+    ys[3] -= 10.0;
+    (xs, ys)
+}
+
+#[test]
+fn test_m_estimators_resist_outliers() {
+    let (xs, ys) = contaminated_line();
+    let (ols_slope, _): (f64, f64) = ::linear_regression(&xs, &ys).unwrap();
+
+    let mut weights = [0.0; 20];
+    let huber = MEstimator::new(Loss::huber())
+        .unwrap()
+        .fit(&xs, &ys, &mut weights)
+        .unwrap();
+    assert!((huber.slope - 0.5).abs() < (ols_slope - 0.5).abs());
+    assert!(weights[15] < 0.1);
+    assert_eq!(1.0, weights[0]);
+
+    // the weights of the result reproduce it
+    let xyws: Vec<(f64, f64, f64)> = (0..20).map(|i| (xs[i], ys[i], weights[i])).collect();
+    let (slope, intercept): (f64, f64) = ::weighted_linear_regression_of(&xyws).unwrap();
+    assert!((slope - huber.slope).abs() < 1e-8);
+    assert!((intercept - huber.intercept).abs() < 1e-8);
+
+    let tukey = MEstimator::new(Loss::tukey())
+        .unwrap()
+        .fit(&xs, &ys, &mut weights)
+        .unwrap();
+    assert_eq!(0.0, weights[15]);
+    assert_eq!(0.0, weights[3]);
+    assert!((tukey.slope - 0.5).abs() < 0.01);
+    assert!((tukey.intercept - 1.0).abs() < 0.1);
+    assert!(tukey.scale < 0.3);
+    assert!(tukey.iterations > huber.iterations);
+}
+
+#[test]
+fn test_m_estimator_exact_fit() {
+    let xys: [(u8, u8); 7] = [(1, 3), (2, 5), (3, 7), (4, 30), (5, 11), (6, 13), (7, 15)];
+
+    // the scale shrinks towards zero as the line settles on the clean points
+    let mut weights = [0.0; 7];
+    let fit = MEstimator::new(Loss::tukey())
+        .unwrap()
+        .fit_of(&xys, &mut weights)
+        .unwrap();
+
+    assert!((fit.slope - 2.0f64).abs() < 1e-9);
+    assert!((fit.intercept - 1.0).abs() < 1e-9);
+    assert!(fit.scale < 1e-6);
+    assert_eq!(0.0, weights[3]);
+}
+
+#[test]
+fn test_m_estimator_errors() {
+    let (xs, ys) = contaminated_line();
+    let mut weights = [0.0; 20];
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        MEstimator::new(Loss::Huber(0.0))
+    );
+    let huber = MEstimator::new(Loss::huber()).unwrap();
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        huber.fit(&xs, &ys[..19], &mut weights)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        huber.fit(&xs, &ys, &mut weights[..19])
+    );
+    assert_eq!(
+        Err(LinRegError::NotConverged),
+        huber.with_max_iterations(1).fit(&xs, &ys, &mut weights)
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        huber.fit(&[1.0, 1.0], &[1.0, 2.0], &mut weights)
+    );
+}
@@ -0,0 +1,472 @@
+use num_traits::Float;
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+#[cfg(all(test, not(feature = "alloc")))]
+use std::vec::Vec;
+
+use error::LinRegError;
+
+/// Adds an observation to a triangular factor `R` and `Q^T y`
+///
+/// Each row of `r` is a row of the upper triangular matrix. The new `row` of
+/// the design matrix is eliminated column by column with Householder
+/// reflections of the stacked system `[R; row]`, which leaves `row` zeroed.
+///
+/// Returns the part of `y` that no linear combination of the columns can
+/// explain, whose square adds to the residual sum of squares.
+pub(crate) fn add_row<F, R>(r: &mut [R], qty: &mut [F], row: &mut [F], mut y: F) -> F
+where
+    F: Float,
+    R: AsMut<[F]>,
+{
+    let two = F::one() + F::one();
+
+    for k in 0..qty.len() {
+        if row[k] == F::zero() {
+            continue;
+        }
+
+        let r_k = r[k].as_mut();
+
+        // reflect (r_kk, row_k) onto (alpha, 0), avoiding cancellation
+        let a = r_k[k];
+        let norm = a.hypot(row[k]);
+        let alpha = if a > F::zero() { -norm } else { norm };
+        let v0 = a - alpha;
+        let v1 = row[k];
+        let vtv = v0 * v0 + v1 * v1;
+
+        r_k[k] = alpha;
+        row[k] = F::zero();
+
+        for j in k + 1..qty.len() {
+            let f = two * (v0 * r_k[j] + v1 * row[j]) / vtv;
+            r_k[j] = r_k[j] - f * v0;
+            row[j] = row[j] - f * v1;
+        }
+
+        let f = two * (v0 * qty[k] + v1 * y) / vtv;
+        qty[k] = qty[k] - f * v0;
+        y = y - f * v1;
+    }
+
+    y
+}
+
+/// Checks that the triangular factor has full rank
+pub(crate) fn check_rank<F, R>(r: &[R], n: usize) -> Result<(), LinRegError>
+where
+    F: Float,
+    R: AsRef<[F]>,
+{
+    let p = r.len();
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+
+    let max_diag = (0..p).fold(F::zero(), |m, k| m.max(r[k].as_ref()[k].abs()));
+    let scale = F::from(n.max(p)).ok_or(LinRegError::CountNotRepresentable)?;
+    let tolerance = max_diag * F::epsilon() * scale;
+
+    for (k, r_k) in r.iter().enumerate() {
+        let d = r_k.as_ref()[k];
+        if !d.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+        if d.abs() <= tolerance {
+            return Err(LinRegError::RankDeficient);
+        }
+    }
+
+    Ok(())
+}
+
+/// Solves `R beta = rhs` by back substitution, overwriting `rhs` with `beta`
+pub(crate) fn solve_upper<F, R>(r: &[R], rhs: &mut [F])
+where
+    F: Float,
+    R: AsRef<[F]>,
+{
+    for k in (0..rhs.len()).rev() {
+        let r_k = r[k].as_ref();
+        let mut sum = rhs[k];
+        for j in k + 1..rhs.len() {
+            sum = sum - r_k[j] * rhs[j];
+        }
+        rhs[k] = sum / r_k[k];
+    }
+}
+
+/// Calculates the diagonal of `(R^T R)^-1`, the unscaled variances of the
+/// coefficients
+///
+/// Column `j` of `R^-1` is found by back substitution on the unit vector,
+/// using `column` as scratch space. The diagonal is the sum of squares of
+/// the rows of `R^-1`.
+fn unscaled_variances<F, R>(r: &[R], column: &mut [F], variances: &mut [F])
+where
+    F: Float,
+    R: AsRef<[F]>,
+{
+    for v in variances.iter_mut() {
+        *v = F::zero();
+    }
+
+    for j in 0..column.len() {
+        for (i, c) in column.iter_mut().enumerate() {
+            *c = if i == j { F::one() } else { F::zero() };
+        }
+        solve_upper(r, column);
+
+        for (v, c) in variances.iter_mut().zip(column.iter()) {
+            *v = *v + *c * *c;
+        }
+    }
+}
+
+/// Residual degrees of freedom and standard error
+fn residual_std_error<F: Float>(n: usize, p: usize, rss: F) -> Result<(usize, F), LinRegError> {
+    let df = n.saturating_sub(p);
+    if df == 0 {
+        return Ok((0, F::nan()));
+    }
+
+    let df_f = F::from(df).ok_or(LinRegError::CountNotRepresentable)?;
+    Ok((df, (rss / df_f).sqrt()))
+}
+
+/// Multiple linear regression with `P` coefficients
+///
+/// Fits `y = X beta` for rows of `P` predictors by least squares. The rows
+/// are folded one at a time into the triangular factor of a Householder QR
+/// decomposition, so only `O(P²)` memory is used, all of it on the stack.
+///
+/// There is no implicit intercept: add a predictor that is always one to fit
+/// it.
+///
+/// ```rust
+/// use linreg::MultiRegression;
+///
+/// let mut reg = MultiRegression::<f64, 3>::new();
+/// for &(x1, x2) in &[(0.0, 1.0), (1.0, 0.0), (2.0, 3.0), (3.0, 1.0), (4.0, 2.0)] {
+///     reg.push([1.0, x1, x2], 1.0 + 2.0 * x1 - 3.0 * x2);
+/// }
+///
+/// let fit = reg.fit().unwrap();
+/// assert!((fit.coefficients[0] - 1.0).abs() < 1e-12);
+/// assert!((fit.coefficients[1] - 2.0).abs() < 1e-12);
+/// assert!((fit.coefficients[2] + 3.0).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MultiRegression<F, const P: usize> {
+    // upper triangular factor R
+    pub(crate) r: [[F; P]; P],
+    // Q^T y
+    pub(crate) qty: [F; P],
+    // residual sum of squares
+    pub(crate) rss: F,
+    pub(crate) n: usize,
+}
+
+/// Coefficients of a multiple linear regression with `P` coefficients
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MultiRegressionResult<F, const P: usize> {
+    /// Fitted coefficients, in the order of the predictors
+    pub coefficients: [F; P],
+    /// Standard errors of the coefficients, `NaN` if `df` is zero
+    pub std_errors: [F; P],
+    /// Number of data points
+    pub n: usize,
+    /// Residual degrees of freedom, `n - P`
+    pub df: usize,
+    /// Sum of squared residuals
+    pub rss: F,
+    /// Estimated standard deviation of the residuals, `NaN` if `df` is zero
+    pub residual_std_error: F,
+}
+
+impl<F: Float, const P: usize> Default for MultiRegression<F, P> {
+    fn default() -> Self {
+        MultiRegression::new()
+    }
+}
+
+impl<F: Float, const P: usize> MultiRegression<F, P> {
+    /// Creates an accumulator without any data points
+    pub fn new() -> Self {
+        MultiRegression {
+            r: [[F::zero(); P]; P],
+            qty: [F::zero(); P],
+            rss: F::zero(),
+            n: 0,
+        }
+    }
+
+    /// Adds a data point with the predictors `row` and the response `y`
+    pub fn push(&mut self, mut row: [F; P], y: F) {
+        let rest = add_row(&mut self.r, &mut self.qty, &mut row, y);
+        self.rss = self.rss + rest * rest;
+        self.n += 1;
+    }
+
+    /// Number of data points pushed so far
+    pub fn count(&self) -> usize {
+        self.n
+    }
+
+    /// Solves for the coefficients and their standard errors
+    ///
+    /// Returns an error if
+    ///
+    /// * no data points were pushed (`EmptyInput`)
+    /// * the predictors are linearly dependent, including the case of fewer
+    ///   data points than coefficients (`RankDeficient`)
+    /// * a NaN or infinite value occurred (`NonFinite`)
+    pub fn fit(&self) -> Result<MultiRegressionResult<F, P>, LinRegError> {
+        check_rank(&self.r, self.n)?;
+
+        let mut coefficients = self.qty;
+        solve_upper(&self.r, &mut coefficients);
+
+        let (df, s) = residual_std_error(self.n, P, self.rss)?;
+
+        let mut std_errors = [F::zero(); P];
+        let mut column = [F::zero(); P];
+        unscaled_variances(&self.r, &mut column, &mut std_errors);
+        for se in std_errors.iter_mut() {
+            *se = s * se.sqrt();
+        }
+
+        Ok(MultiRegressionResult {
+            coefficients,
+            std_errors,
+            n: self.n,
+            df,
+            rss: self.rss,
+            residual_std_error: s,
+        })
+    }
+}
+
+impl<F: Float, const P: usize> MultiRegressionResult<F, P> {
+    /// Evaluates the fitted model for the predictors `row`
+    pub fn predict(&self, row: &[F; P]) -> F {
+        row.iter()
+            .zip(self.coefficients.iter())
+            .fold(F::zero(), |sum, (&x, &b)| sum + x * b)
+    }
+}
+
+/// Multiple linear regression from rows of predictors and a slice of
+/// responses
+///
+/// Convenience wrapper around [`MultiRegression`](struct.MultiRegression.html).
+/// Fails for the same reasons as its `fit` method, or if `rows` and `ys`
+/// differ in length (`LengthMismatch`).
+pub fn multiple_linear_regression<X, Y, F, const P: usize>(
+    rows: &[[X; P]],
+    ys: &[Y],
+) -> Result<MultiRegressionResult<F, P>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if rows.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    let mut reg = MultiRegression::new();
+    for (row, y) in rows.iter().zip(ys.iter()) {
+        let mut converted = [F::zero(); P];
+        for (c, x) in converted.iter_mut().zip(row.iter()) {
+            *c = x.clone().into();
+        }
+        reg.push(converted, y.clone().into());
+    }
+
+    reg.fit()
+}
+
+/// Multiple linear regression with a number of coefficients chosen at runtime
+///
+/// Works like [`MultiRegression`](struct.MultiRegression.html), but stores
+/// its `O(p²)` state on the heap. Requires the `alloc` feature.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynMultiRegression<F> {
+    r: Vec<Vec<F>>,
+    qty: Vec<F>,
+    rss: F,
+    n: usize,
+    // scratch space for the row being added
+    row: Vec<F>,
+}
+
+/// Coefficients of a multiple linear regression with a runtime number of
+/// coefficients
+#[cfg(feature = "alloc")]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DynMultiRegressionResult<F> {
+    /// Fitted coefficients, in the order of the predictors
+    pub coefficients: Vec<F>,
+    /// Standard errors of the coefficients, `NaN` if `df` is zero
+    pub std_errors: Vec<F>,
+    /// Number of data points
+    pub n: usize,
+    /// Residual degrees of freedom, `n - p`
+    pub df: usize,
+    /// Sum of squared residuals
+    pub rss: F,
+    /// Estimated standard deviation of the residuals, `NaN` if `df` is zero
+    pub residual_std_error: F,
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Float> DynMultiRegression<F> {
+    /// Creates an accumulator for `p` coefficients
+    pub fn new(p: usize) -> Self {
+        DynMultiRegression {
+            r: vec![vec![F::zero(); p]; p],
+            qty: vec![F::zero(); p],
+            rss: F::zero(),
+            n: 0,
+            row: vec![F::zero(); p],
+        }
+    }
+
+    /// Number of coefficients
+    pub fn coefficients(&self) -> usize {
+        self.qty.len()
+    }
+
+    /// Adds a data point with the predictors `row` and the response `y`
+    ///
+    /// Returns `LengthMismatch` if `row` does not hold one value per
+    /// coefficient.
+    pub fn push(&mut self, row: &[F], y: F) -> Result<(), LinRegError> {
+        if row.len() != self.qty.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        self.row.copy_from_slice(row);
+        let rest = add_row(&mut self.r, &mut self.qty, &mut self.row, y);
+        self.rss = self.rss + rest * rest;
+        self.n += 1;
+        Ok(())
+    }
+
+    /// Number of data points pushed so far
+    pub fn count(&self) -> usize {
+        self.n
+    }
+
+    /// Solves for the coefficients and their standard errors
+    ///
+    /// Fails for the same reasons as
+    /// [`MultiRegression::fit`](struct.MultiRegression.html#method.fit).
+    pub fn fit(&self) -> Result<DynMultiRegressionResult<F>, LinRegError> {
+        let p = self.qty.len();
+        check_rank(&self.r, self.n)?;
+
+        let mut coefficients = self.qty.clone();
+        solve_upper(&self.r, &mut coefficients);
+
+        let (df, s) = residual_std_error(self.n, p, self.rss)?;
+
+        let mut std_errors = vec![F::zero(); p];
+        let mut column = vec![F::zero(); p];
+        unscaled_variances(&self.r, &mut column, &mut std_errors);
+        for se in std_errors.iter_mut() {
+            *se = s * se.sqrt();
+        }
+
+        Ok(DynMultiRegressionResult {
+            coefficients,
+            std_errors,
+            n: self.n,
+            df,
+            rss: self.rss,
+            residual_std_error: s,
+        })
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<F: Float> DynMultiRegressionResult<F> {
+    /// Evaluates the fitted model for the predictors `row`
+    ///
+    /// Extra or missing predictors are ignored.
+    pub fn predict(&self, row: &[F]) -> F {
+        row.iter()
+            .zip(self.coefficients.iter())
+            .fold(F::zero(), |sum, (&x, &b)| sum + x * b)
+    }
+}
+
+#[test]
+fn test_matches_simple_regression() {
+    let xs: Vec<f64> = vec![0.5, 1.5, 2.0, 3.25, 4.0, 6.5, 7.0];
+    let ys: Vec<f64> = vec![1.0, 2.5, 2.0, 4.0, 4.5, 7.0, 6.5];
+    let rows: Vec<[f64; 2]> = xs.iter().map(|&x| [1.0, x]).collect();
+
+    let fit: MultiRegressionResult<f64, 2> = multiple_linear_regression(&rows, &ys).unwrap();
+    let simple: ::RegressionResult<f64> = ::linear_regression_stats(&xs, &ys).unwrap();
+
+    assert_eq!(5, fit.df);
+    assert!((fit.coefficients[0] - simple.intercept).abs() < 1e-12);
+    assert!((fit.coefficients[1] - simple.slope).abs() < 1e-12);
+    assert!((fit.std_errors[0] - simple.intercept_std_error).abs() < 1e-12);
+    assert!((fit.std_errors[1] - simple.slope_std_error).abs() < 1e-12);
+    assert!((fit.rss - simple.sse).abs() < 1e-12);
+    assert!((fit.predict(&[1.0, 2.0]) - simple.predict(2.0)).abs() < 1e-12);
+}
+
+#[test]
+fn test_multi_errors() {
+    let mut reg = MultiRegression::<f64, 2>::new();
+    assert_eq!(Err(LinRegError::EmptyInput), reg.fit());
+
+    // the second predictor is twice the first
+    reg.push([1.0, 2.0], 1.0);
+    reg.push([2.0, 4.0], 3.0);
+    reg.push([3.0, 6.0], 4.0);
+    assert_eq!(Err(LinRegError::RankDeficient), reg.fit());
+
+    let rows: Vec<[f64; 2]> = vec![[1.0, 0.0]];
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        multiple_linear_regression::<_, _, f64, 2>(&rows, &[1.0, 2.0])
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_dyn_matches_const() {
+    let data = [
+        ([1.0, 0.3, 2.0], 1.2),
+        ([1.0, 1.1, 0.5], 2.9),
+        ([1.0, 2.4, 1.5], 3.1),
+        ([1.0, 3.0, 3.5], 1.0),
+        ([1.0, 4.2, 0.1], 7.7),
+        ([1.0, 5.5, 2.2], 6.0),
+    ];
+
+    let mut fixed = MultiRegression::<f64, 3>::new();
+    let mut dynamic = DynMultiRegression::new(3);
+    for &(row, y) in &data {
+        fixed.push(row, y);
+        dynamic.push(&row, y).unwrap();
+    }
+
+    let fixed = fixed.fit().unwrap();
+    let dynamic = dynamic.fit().unwrap();
+
+    assert_eq!(&fixed.coefficients[..], &dynamic.coefficients[..]);
+    assert_eq!(&fixed.std_errors[..], &dynamic.std_errors[..]);
+    assert_eq!(3, fixed.df);
+
+    let mut reg = DynMultiRegression::<f64>::new(3);
+    assert_eq!(Err(LinRegError::LengthMismatch), reg.push(&[1.0], 1.0));
+}
@@ -0,0 +1,326 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+use result::RegressionResult;
+
+/// Linear regression over a stream of data points
+///
+/// Keeps the means and co-moments of all points seen so far and updates them
+/// with Welford's numerically stable algorithm. Memory usage is constant and
+/// nothing is allocated, so samples can be pushed indefinitely.
+///
+/// ```rust
+/// use linreg::OnlineRegression;
+///
+/// let mut reg = OnlineRegression::new();
+/// for &(x, y) in &[(1.0, 2.0), (2.0, 4.0), (3.0, 5.0), (4.0, 4.0), (5.0, 5.0)] {
+///     reg.push(x, y);
+/// }
+///
+/// assert_eq!(5, reg.count());
+/// assert!((reg.slope().unwrap() - 0.6f64).abs() < 1e-12);
+/// assert!((reg.intercept().unwrap() - 2.2f64).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OnlineRegression<F> {
+    n: usize,
+    x_mean: F,
+    y_mean: F,
+    // SUM (x-mean(x))^2
+    sxx: F,
+    // SUM (y-mean(y))^2
+    syy: F,
+    // SUM (x-mean(x)) (y-mean(y))
+    sxy: F,
+}
+
+impl<F: Float> Default for OnlineRegression<F> {
+    fn default() -> Self {
+        OnlineRegression::new()
+    }
+}
+
+impl<F: Float> OnlineRegression<F> {
+    /// Creates an accumulator without any data points
+    pub fn new() -> Self {
+        OnlineRegression {
+            n: 0,
+            x_mean: F::zero(),
+            y_mean: F::zero(),
+            sxx: F::zero(),
+            syy: F::zero(),
+            sxy: F::zero(),
+        }
+    }
+
+    /// Creates an accumulator from the means and co-moments of `n` points
+    #[cfg_attr(not(feature = "rayon"), allow(dead_code))]
+    pub(crate) fn from_moments(n: usize, x_mean: F, y_mean: F, sxx: F, syy: F, sxy: F) -> Self {
+        OnlineRegression {
+            n,
+            x_mean,
+            y_mean,
+            sxx,
+            syy,
+            sxy,
+        }
+    }
+
+    /// Adds a data point
+    pub fn push(&mut self, x: F, y: F) {
+        self.n += 1;
+        // precision loss only matters beyond 2^24 (f32) or 2^53 (f64) points
+        let n = F::from(self.n).unwrap_or_else(F::infinity);
+
+        let dx = x - self.x_mean;
+        let dy = y - self.y_mean;
+        self.x_mean = self.x_mean + dx / n;
+        self.y_mean = self.y_mean + dy / n;
+
+        self.sxx = self.sxx + dx * (x - self.x_mean);
+        self.syy = self.syy + dy * (y - self.y_mean);
+        self.sxy = self.sxy + dx * (y - self.y_mean);
+    }
+
+    /// Removes a data point that was pushed before
+    ///
+    /// This reverses [`push`](#method.push), which allows regressions over a
+    /// sliding window. Removing a point that was never pushed leaves the
+    /// accumulator in a meaningless state, and removing one far away from
+    /// the remaining points loses precision.
+    pub fn remove(&mut self, x: F, y: F) {
+        if self.n <= 1 {
+            *self = OnlineRegression::new();
+            return;
+        }
+
+        self.n -= 1;
+        let n = F::from(self.n).unwrap_or_else(F::infinity);
+
+        let dx = x - self.x_mean;
+        let dy = y - self.y_mean;
+        let x_mean = self.x_mean - dx / n;
+        let y_mean = self.y_mean - dy / n;
+
+        // a sum that all but cancels out is left with the rounding errors of
+        // the updates that built it, which means the x values are all equal
+        let sxx = self.sxx - dx * (x - x_mean);
+        if sxx <= self.sxx * F::epsilon() * (n + F::one()) {
+            self.sxx = F::zero();
+            self.sxy = F::zero();
+        } else {
+            self.sxx = sxx;
+            self.sxy = self.sxy - dx * (y - y_mean);
+        }
+        // rounding errors must not make the squared sum negative
+        self.syy = (self.syy - dy * (y - y_mean)).max(F::zero());
+
+        self.x_mean = x_mean;
+        self.y_mean = y_mean;
+    }
+
+    /// Combines the data points of `other` into this accumulator
+    ///
+    /// Uses the pairwise update of Chan, Golub and LeVeque, so accumulators
+    /// filled on different threads or machines can be merged into the result
+    /// a single accumulator would have produced for all data points.
+    pub fn merge(&mut self, other: &OnlineRegression<F>) {
+        if other.n == 0 {
+            return;
+        }
+        if self.n == 0 {
+            *self = *other;
+            return;
+        }
+
+        let n_a = F::from(self.n).unwrap_or_else(F::infinity);
+        let n_b = F::from(other.n).unwrap_or_else(F::infinity);
+        self.n += other.n;
+        let n = n_a + n_b;
+
+        let dx = other.x_mean - self.x_mean;
+        let dy = other.y_mean - self.y_mean;
+        // weight of the correction term for the shifted means
+        let f = n_a * n_b / n;
+
+        self.x_mean = self.x_mean + dx * n_b / n;
+        self.y_mean = self.y_mean + dy * n_b / n;
+
+        self.sxx = self.sxx + other.sxx + dx * dx * f;
+        self.syy = self.syy + other.syy + dy * dy * f;
+        self.sxy = self.sxy + other.sxy + dx * dy * f;
+    }
+
+    /// Number of data points currently included
+    pub fn count(&self) -> usize {
+        self.n
+    }
+
+    /// Mean of the x values
+    pub fn x_mean(&self) -> F {
+        self.x_mean
+    }
+
+    /// Mean of the y values
+    pub fn y_mean(&self) -> F {
+        self.y_mean
+    }
+
+    /// Slope of the regression line over all points so far
+    ///
+    /// Returns an error if no points were pushed (`EmptyInput`), all x values
+    /// are identical (`ConstantX`) or the slope is not finite (`NonFinite`).
+    pub fn slope(&self) -> Result<F, LinRegError> {
+        self.line().map(|(slope, _)| slope)
+    }
+
+    /// Intercept of the regression line over all points so far
+    ///
+    /// Fails for the same reasons as [`slope`](#method.slope).
+    pub fn intercept(&self) -> Result<F, LinRegError> {
+        self.line().map(|(_, intercept)| intercept)
+    }
+
+    /// Full regression statistics over all points so far
+    ///
+    /// Unlike [`slope`](#method.slope) and [`intercept`](#method.intercept),
+    /// this evaluates significance tests and should not be called for every
+    /// sample on slow hardware.
+    pub fn result(&self) -> Result<RegressionResult<F>, LinRegError> {
+        RegressionResult::from_moments(
+            self.n,
+            self.x_mean,
+            self.y_mean,
+            self.sxx,
+            self.syy,
+            self.sxy,
+        )
+    }
+
+    /// Slope and intercept without the remaining statistics
+    fn line(&self) -> Result<(F, F), LinRegError> {
+        if self.n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+        if self.sxx == F::zero() {
+            return Err(LinRegError::ConstantX);
+        }
+
+        let slope = self.sxy / self.sxx;
+        let intercept = self.y_mean - slope * self.x_mean;
+
+        if !slope.is_finite() || !intercept.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+
+        Ok((slope, intercept))
+    }
+}
+
+#[test]
+fn test_online_matches_batch() {
+    let xs: Vec<f64> = vec![0.5, 1.5, 2.0, 3.25, 4.0, 6.5, 7.0];
+    let ys: Vec<f64> = vec![1.0, 2.5, 2.0, 4.0, 4.5, 7.0, 6.5];
+
+    let mut reg = OnlineRegression::new();
+    for (&x, &y) in xs.iter().zip(ys.iter()) {
+        reg.push(x, y);
+    }
+
+    let batch: RegressionResult<f64> = ::linear_regression_stats(&xs, &ys).unwrap();
+    let online = reg.result().unwrap();
+
+    assert_eq!(7, reg.count());
+    assert!((online.slope - batch.slope).abs() < 1e-12);
+    assert!((online.intercept - batch.intercept).abs() < 1e-12);
+    assert!((online.r_squared - batch.r_squared).abs() < 1e-12);
+    assert!((online.sse - batch.sse).abs() < 1e-12);
+}
+
+#[test]
+fn test_online_errors() {
+    let mut reg = OnlineRegression::<f32>::new();
+    assert_eq!(Err(LinRegError::EmptyInput), reg.slope());
+
+    reg.push(1.0, 1.0);
+    reg.push(1.0, 3.0);
+    assert_eq!(Err(LinRegError::ConstantX), reg.intercept());
+
+    reg.push(2.0, 2.0);
+    assert_eq!(Ok(0.0), reg.slope());
+}
+
+#[test]
+fn test_online_remove() {
+    let points: Vec<(f64, f64)> = vec![(1.0, 3.0), (2.0, 4.0), (3.0, 8.0), (4.0, 9.0), (5.0, 9.5)];
+
+    let mut reg = OnlineRegression::new();
+    for &(x, y) in &points {
+        reg.push(x, y);
+    }
+    reg.remove(1.0, 3.0);
+    reg.remove(2.0, 4.0);
+
+    let (slope, intercept): (f64, f64) = ::linear_regression_of(&points[2..]).unwrap();
+    assert_eq!(3, reg.count());
+    assert!((reg.slope().unwrap() - slope).abs() < 1e-12);
+    assert!((reg.intercept().unwrap() - intercept).abs() < 1e-12);
+
+    for &(x, y) in &points[2..] {
+        reg.remove(x, y);
+    }
+    assert_eq!(OnlineRegression::new(), reg);
+}
+
+#[test]
+fn test_online_remove_to_constant_x() {
+    // removing the other points must not leave a rounding residue
+    let mut reg = OnlineRegression::new();
+    for &(x, y) in &[(0.3, 1.0), (0.7, 2.0), (0.1, 5.0), (0.1, 6.0)] {
+        reg.push(x, y);
+    }
+    reg.remove(0.3, 1.0);
+    reg.remove(0.7, 2.0);
+
+    assert_eq!(Err(LinRegError::ConstantX), reg.slope());
+    assert!((reg.y_mean() - 5.5f64).abs() < 1e-12);
+
+    reg.push(0.2, 7.0);
+    assert!((reg.slope().unwrap() - 15.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_online_merge() {
+    let xs: Vec<f64> = vec![0.5, 1.5, 2.0, 3.25, 4.0, 6.5, 7.0, 8.0, 9.5];
+    let ys: Vec<f64> = vec![1.0, 2.5, 2.0, 4.0, 4.5, 7.0, 6.5, 8.5, 9.0];
+
+    let mut a = OnlineRegression::new();
+    let mut b = OnlineRegression::new();
+    for (i, (&x, &y)) in xs.iter().zip(ys.iter()).enumerate() {
+        if i < 4 {
+            a.push(x, y);
+        } else {
+            b.push(x, y);
+        }
+    }
+
+    let mut empty = OnlineRegression::new();
+    empty.merge(&a);
+    assert_eq!(a, empty);
+
+    a.merge(&b);
+    a.merge(&OnlineRegression::new());
+
+    let batch: RegressionResult<f64> = ::linear_regression_stats(&xs, &ys).unwrap();
+    let merged = a.result().unwrap();
+
+    assert_eq!(9, a.count());
+    assert!((merged.slope - batch.slope).abs() < 1e-12);
+    assert!((merged.intercept - batch.intercept).abs() < 1e-12);
+    assert!((merged.sxx - batch.sxx).abs() < 1e-12);
+    assert!((merged.syy - batch.syy).abs() < 1e-12);
+    assert!((merged.sxy - batch.sxy).abs() < 1e-12);
+}
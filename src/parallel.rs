@@ -0,0 +1,148 @@
+use num_traits::Float;
+use rayon::prelude::*;
+use std::vec::Vec;
+
+use error::LinRegError;
+use online::OnlineRegression;
+use stats::{centered_sums, common_x, means};
+
+/// Number of data points each task accumulates on its own
+///
+/// The chunking does not depend on the number of threads, so results are
+/// reproducible across machines.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Means and co-moments of a chunk of data points, using two passes
+fn chunk_stats<I, F>(xys: I) -> Result<OnlineRegression<F>, LinRegError>
+where
+    I: Iterator<Item = (F, F)> + Clone,
+    F: Float,
+{
+    let (n, mut x_mean, y_mean) = means(xys.clone())?;
+    let (mut sxx, syy, mut sxy) = centered_sums(xys.clone(), x_mean, y_mean);
+
+    // keep constant chunks exact, so merging them cannot invent a slope
+    if let Some(x0) = common_x(xys) {
+        x_mean = x0;
+        sxx = F::zero();
+        sxy = F::zero();
+    }
+
+    Ok(OnlineRegression::from_moments(
+        n, x_mean, y_mean, sxx, syy, sxy,
+    ))
+}
+
+/// Merges the chunk statistics in order
+fn merge_chunks<F: Float>(chunks: Vec<OnlineRegression<F>>) -> Result<(F, F), LinRegError> {
+    let mut total = OnlineRegression::new();
+    for chunk in &chunks {
+        total.merge(chunk);
+    }
+
+    Ok((total.slope()?, total.intercept()?))
+}
+
+/// Linear regression from two slices, calculated in parallel
+///
+/// Splits the slices into chunks that are accumulated on the rayon thread
+/// pool and merged in a fixed order, so the result is deterministic. It can
+/// differ from [`try_linear_regression`](fn.try_linear_regression.html) in
+/// the last bits due to the different order of summation. Fails for the same
+/// reasons.
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn par_linear_regression<X, Y, F>(xs: &[X], ys: &[Y]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F> + Sync,
+    Y: Clone + Into<F> + Sync,
+    F: Float + Send,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+    if xs.is_empty() {
+        return Err(LinRegError::EmptyInput);
+    }
+    F::from(xs.len()).ok_or(LinRegError::CountNotRepresentable)?;
+
+    let chunks = xs
+        .par_chunks(CHUNK_SIZE)
+        .zip(ys.par_chunks(CHUNK_SIZE))
+        .map(|(xs, ys)| {
+            chunk_stats(
+                xs.iter()
+                    .map(|x| x.clone().into())
+                    .zip(ys.iter().map(|y| y.clone().into())),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    merge_chunks(chunks)
+}
+
+/// Linear regression from tuples, calculated in parallel
+///
+/// The tuple counterpart of
+/// [`par_linear_regression`](fn.par_linear_regression.html).
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn par_linear_regression_of<X, Y, F>(xys: &[(X, Y)]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F> + Sync,
+    Y: Clone + Into<F> + Sync,
+    F: Float + Send,
+{
+    if xys.is_empty() {
+        return Err(LinRegError::EmptyInput);
+    }
+    F::from(xys.len()).ok_or(LinRegError::CountNotRepresentable)?;
+
+    let chunks = xys
+        .par_chunks(CHUNK_SIZE)
+        .map(|xys| {
+            chunk_stats(
+                xys.iter()
+                    .map(|(x, y)| (x.clone().into(), y.clone().into())),
+            )
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    merge_chunks(chunks)
+}
+
+#[test]
+fn test_parallel_matches_sequential() {
+    let n: u32 = 3 * CHUNK_SIZE as u32 + 17;
+    let xs: Vec<f64> = (0..n).map(|i| f64::from(i) * 1e-3).collect();
+    let ys: Vec<f64> = (0..n)
+        .map(|i| 0.25 * f64::from(i) * 1e-3 - 4.0 + f64::from(i % 13) * 0.01)
+        .collect();
+
+    let (slope, intercept): (f64, f64) = ::try_linear_regression(&xs, &ys).unwrap();
+    let (par_slope, par_intercept): (f64, f64) = par_linear_regression(&xs, &ys).unwrap();
+
+    assert!((slope - par_slope).abs() < 1e-9);
+    assert!((intercept - par_intercept).abs() < 1e-9);
+
+    let tuples: Vec<(f64, f64)> = xs.iter().cloned().zip(ys.iter().cloned()).collect();
+    assert_eq!(
+        Ok((par_slope, par_intercept)),
+        par_linear_regression_of(&tuples)
+    );
+}
+
+#[test]
+fn test_parallel_errors() {
+    let xs: Vec<f32> = vec![2.0; CHUNK_SIZE + 1];
+    let ys: Vec<f32> = (0..CHUNK_SIZE + 1).map(|i| i as f32).collect();
+
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        par_linear_regression::<_, _, f32>(&xs, &ys)
+    );
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        par_linear_regression::<_, _, f32>(&xs, &ys[1..])
+    );
+}
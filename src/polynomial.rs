@@ -0,0 +1,251 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+use multi::MultiRegression;
+use stats::{centered_sums, common_x, means};
+
+/// Least squares polynomial with `COEFFS = degree + 1` coefficients
+///
+/// The fit is done on `t = (x - x_shift) / x_scale`, which maps the x values
+/// onto `[-1, 1]`, so the powers of `t` stay well conditioned even for x
+/// values far from zero. [`predict`](#method.predict) evaluates the fit in
+/// this basis; `coefficients` holds the same polynomial in powers of `x`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolynomialRegressionResult<F, const COEFFS: usize> {
+    /// Coefficients of `x^0, x^1, ..., x^(COEFFS-1)`
+    pub coefficients: [F; COEFFS],
+    /// Number of data points
+    pub n: usize,
+    /// Residual degrees of freedom, `n - COEFFS`
+    pub df: usize,
+    /// Sum of squared residuals
+    pub rss: F,
+    /// Coefficient of determination, `NaN` if all y values are identical
+    pub r_squared: F,
+    /// Estimated standard deviation of the residuals, `NaN` if `df` is zero
+    pub residual_std_error: F,
+    // coefficients of the powers of t
+    scaled: [F; COEFFS],
+    x_shift: F,
+    x_scale: F,
+}
+
+impl<F: Float, const COEFFS: usize> PolynomialRegressionResult<F, COEFFS> {
+    /// Evaluates the fitted polynomial at `x`
+    pub fn predict(&self, x: F) -> F {
+        let t = (x - self.x_shift) / self.x_scale;
+        self.scaled
+            .iter()
+            .rev()
+            .fold(F::zero(), |sum, &c| sum * t + c)
+    }
+}
+
+/// Fits the polynomial to the points returned by `xys`
+///
+/// Takes a closure because the points are traversed several times: for the
+/// means, for the range of x, for the spread of y and for the QR fit.
+fn fit_polynomial<I, G, F, const COEFFS: usize>(
+    xys: G,
+) -> Result<PolynomialRegressionResult<F, COEFFS>, LinRegError>
+where
+    G: Fn() -> I,
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    if COEFFS == 0 {
+        return Err(LinRegError::InvalidParameter);
+    }
+
+    let (n, x_shift, y_mean) = means(xys())?;
+
+    // max |x-mean(x)|
+    let x_range = xys().fold(F::zero(), |r, (x, _)| r.max((x - x_shift).abs()));
+    let (_, syy, _) = centered_sums(xys(), x_shift, y_mean);
+
+    if !x_shift.is_finite() || !x_range.is_finite() {
+        return Err(LinRegError::NonFinite);
+    }
+
+    let x_scale = if common_x(xys()).is_none() {
+        x_range
+    } else if COEFFS == 1 {
+        // a constant needs no x values at all
+        F::one()
+    } else {
+        return Err(LinRegError::ConstantX);
+    };
+
+    let mut reg = MultiRegression::<F, COEFFS>::new();
+    for (x, y) in xys() {
+        let t = (x - x_shift) / x_scale;
+        let mut row = [F::one(); COEFFS];
+        for k in 1..COEFFS {
+            row[k] = row[k - 1] * t;
+        }
+        reg.push(row, y);
+    }
+
+    let fit = reg.fit()?;
+
+    // expand SUM c_k t^k with Horner's scheme, multiplying by
+    // t = (x - x_shift) / x_scale at every step
+    let mut coefficients = [F::zero(); COEFFS];
+    for &c in fit.coefficients.iter().rev() {
+        for j in (1..COEFFS).rev() {
+            coefficients[j] = (coefficients[j - 1] - x_shift * coefficients[j]) / x_scale;
+        }
+        coefficients[0] = c - x_shift * coefficients[0] / x_scale;
+    }
+
+    Ok(PolynomialRegressionResult {
+        coefficients,
+        n,
+        df: fit.df,
+        rss: fit.rss,
+        r_squared: F::one() - fit.rss / syy,
+        residual_std_error: fit.residual_std_error,
+        scaled: fit.coefficients,
+        x_shift,
+        x_scale,
+    })
+}
+
+/// Polynomial regression from two slices, with `COEFFS = degree + 1`
+///
+/// The const parameter is the number of coefficients, so a quadratic takes
+/// `COEFFS = 3` and a line `COEFFS = 2`. The polynomial is fitted by least
+/// squares: the x values are centered and scaled and the fit is solved with
+/// a Householder QR decomposition instead of the normal equations, so high
+/// degrees and x values far from zero do not lose precision. Nothing is
+/// allocated.
+///
+/// ```rust
+/// use linreg::{polynomial_regression, PolynomialRegressionResult};
+///
+/// let xs = [0.0, 1.0, 2.0, 3.0, 4.0];
+/// let ys = [1.0, 0.0, 1.0, 4.0, 9.0];
+///
+/// // a quadratic, so COEFFS = degree + 1 = 3
+/// let fit: PolynomialRegressionResult<f64, 3> = polynomial_regression(&xs, &ys).unwrap();
+/// assert!((fit.coefficients[0] - 1.0).abs() < 1e-12);
+/// assert!((fit.coefficients[1] + 2.0).abs() < 1e-12);
+/// assert!((fit.coefficients[2] - 1.0).abs() < 1e-12);
+/// assert!((fit.predict(5.0) - 16.0).abs() < 1e-12);
+/// ```
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * `COEFFS` is zero (`InvalidParameter`)
+/// * all x values are identical and `COEFFS` is larger than one (`ConstantX`)
+/// * there are fewer distinct x values than coefficients (`RankDeficient`)
+/// * a NaN or infinite value occurred (`NonFinite`)
+pub fn polynomial_regression<X, Y, F, const COEFFS: usize>(
+    xs: &[X],
+    ys: &[Y],
+) -> Result<PolynomialRegressionResult<F, COEFFS>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_polynomial(|| {
+        xs.iter()
+            .map(|x| x.clone().into())
+            .zip(ys.iter().map(|y| y.clone().into()))
+    })
+}
+
+/// Polynomial regression from tuples, with `COEFFS = degree + 1`
+///
+/// The tuple counterpart of
+/// [`polynomial_regression`](fn.polynomial_regression.html).
+pub fn polynomial_regression_of<X, Y, F, const COEFFS: usize>(
+    xys: &[(X, Y)],
+) -> Result<PolynomialRegressionResult<F, COEFFS>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_polynomial(|| {
+        xys.iter()
+            .map(|(x, y)| (x.clone().into(), y.clone().into()))
+    })
+}
+
+#[test]
+fn test_polynomial_matches_linear() {
+    let xs: Vec<f64> = vec![0.5, 1.5, 2.0, 3.25, 4.0, 6.5, 7.0];
+    let ys: Vec<f64> = vec![1.0, 2.5, 2.0, 4.0, 4.5, 7.0, 6.5];
+
+    let fit: PolynomialRegressionResult<f64, 2> = polynomial_regression(&xs, &ys).unwrap();
+    let simple: ::RegressionResult<f64> = ::linear_regression_stats(&xs, &ys).unwrap();
+
+    assert_eq!(5, fit.df);
+    assert!((fit.coefficients[0] - simple.intercept).abs() < 1e-12);
+    assert!((fit.coefficients[1] - simple.slope).abs() < 1e-12);
+    assert!((fit.rss - simple.sse).abs() < 1e-12);
+    assert!((fit.r_squared - simple.r_squared).abs() < 1e-12);
+    assert!((fit.predict(3.0) - simple.predict(3.0)).abs() < 1e-12);
+
+    let constant: PolynomialRegressionResult<f64, 1> = polynomial_regression(&xs, &ys).unwrap();
+    assert!((constant.coefficients[0] - simple.y_mean).abs() < 1e-12);
+}
+
+#[test]
+fn test_polynomial_far_from_origin() {
+    // a cubic sampled around x = 1000, where x^3 has no spare digits in f64
+    let xys: Vec<(f64, f64)> = (0..20)
+        .map(|i| {
+            let x = 1000.0 + f64::from(i) * 0.25;
+            let t = x - 1002.0;
+            (x, 0.5 * t * t * t - t * t + 3.0)
+        })
+        .collect();
+
+    let fit: PolynomialRegressionResult<f64, 4> = polynomial_regression_of(&xys).unwrap();
+
+    assert!(fit.rss < 1e-18);
+    assert!((fit.r_squared - 1.0).abs() < 1e-12);
+    for &(x, y) in &xys {
+        assert!((fit.predict(x) - y).abs() < 1e-9);
+    }
+    assert!((fit.coefficients[3] - 0.5).abs() < 1e-9);
+}
+
+#[test]
+fn test_polynomial_errors() {
+    let xs: Vec<f64> = vec![1.0, 2.0, 3.0];
+    let ys: Vec<f64> = vec![2.0, 4.0, 3.0];
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        polynomial_regression::<_, _, f64, 0>(&xs, &ys)
+    );
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        polynomial_regression::<_, _, f64, 2>(&xs, &ys[..2])
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        polynomial_regression::<_, _, f64, 2>(&xs[..0], &ys[..0])
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        polynomial_regression::<_, _, f64, 2>(&[1.0, 1.0], &[2.0, 3.0])
+    );
+    assert_eq!(
+        Err(LinRegError::RankDeficient),
+        polynomial_regression::<_, _, f64, 4>(&xs, &ys)
+    );
+}
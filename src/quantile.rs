@@ -0,0 +1,401 @@
+use num_traits::Float;
+
+use error::LinRegError;
+use lad::fit_quantile;
+#[cfg(test)]
+use ransac::{uniform, SplitMix64};
+
+/// Checks that `tau` lies strictly between 0 and 1
+fn check_quantile<F: Float>(tau: F) -> Result<F, LinRegError> {
+    if tau.is_nan() || tau <= F::zero() || tau >= F::one() {
+        Err(LinRegError::InvalidParameter)
+    } else {
+        Ok(tau)
+    }
+}
+
+/// Quantile regression from two slices
+///
+/// Finds the line below which a share `tau` of the points lie, by minimizing
+/// the sum of the residuals weighted by `tau` above the line and by
+/// `1 - tau` below it. For `tau = 0.5` this is the
+/// [least absolute deviations](fn.lad_regression.html) line; `tau = 0.95`
+/// gives the trend of the 95th percentile.
+///
+/// The fit is exact and uses the same algorithm as
+/// [`lad_regression`](fn.lad_regression.html). `scratch` must hold at least
+/// `xs.len()` pairs; its contents are overwritten, and nothing is allocated.
+///
+/// ```rust
+/// use linreg::quantile_regression;
+///
+/// // latencies whose spread grows over time
+/// let xs = [1.0, 1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 3.0, 3.0, 3.0, 3.0];
+/// let ys = [10.0, 11.0, 12.0, 20.0, 10.0, 12.0, 14.0, 30.0, 10.0, 13.0, 16.0, 40.0];
+///
+/// let mut scratch = [(0.0, 0.0); 12];
+/// let (slope, intercept): (f64, f64) = quantile_regression(&xs, &ys, 0.9, &mut scratch).unwrap();
+/// assert!((slope - 10.0).abs() < 1e-12);
+/// assert!((intercept - 10.0).abs() < 1e-12);
+/// ```
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * `tau` is not strictly between 0 and 1 or `scratch` is too short
+///   (`InvalidParameter`)
+/// * all x values are identical (`ConstantX`)
+/// * a value, the slope or the intercept is NaN or infinite (`NonFinite`)
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn quantile_regression<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    tau: F,
+    scratch: &mut [(F, F)],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_quantile(
+        |i| (xs[i].clone().into(), ys[i].clone().into()),
+        xs.len(),
+        check_quantile(tau)?,
+        scratch,
+    )
+}
+
+/// Quantile regression from tuples
+///
+/// The tuple counterpart of
+/// [`quantile_regression`](fn.quantile_regression.html).
+pub fn quantile_regression_of<X, Y, F>(
+    xys: &[(X, Y)],
+    tau: F,
+    scratch: &mut [(F, F)],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_quantile(
+        |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+        xys.len(),
+        check_quantile(tau)?,
+        scratch,
+    )
+}
+
+/// Regression lines for several quantiles at once
+///
+/// Every quantile is fitted separately with
+/// [`quantile_regression`](fn.quantile_regression.html), so with few points
+/// the line of a higher quantile can cross below that of a lower one. With
+/// [`with_non_crossing`](#method.with_non_crossing), the lines are
+/// rearranged: at the smallest and at the largest x value the fitted values
+/// are sorted in the order of the quantiles, and each line is redrawn
+/// through its sorted values. Lines that do not cross are left unchanged,
+/// and the rearranged ones do not cross anywhere within the range of the x
+/// values.
+///
+/// ```rust
+/// use linreg::QuantileRegression;
+///
+/// let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+/// let ys = [1.0, 4.0, 2.0, 7.0, 3.0, 10.0, 4.0, 13.0];
+///
+/// let quantiles = [0.1, 0.5, 0.9];
+/// let mut lines = [(0.0, 0.0); 3];
+/// let mut scratch = [(0.0, 0.0); 8];
+/// QuantileRegression::new(&quantiles)
+///     .unwrap()
+///     .with_non_crossing(true)
+///     .fit(&xs, &ys, &mut lines, &mut scratch)
+///     .unwrap();
+///
+/// for pair in lines.windows(2) {
+///     let (low, high): ((f64, f64), (f64, f64)) = (pair[0], pair[1]);
+///     assert!(low.1 <= high.1);
+///     assert!(low.1 + 7.0 * low.0 <= high.1 + 7.0 * high.0);
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuantileRegression<'a, F> {
+    quantiles: &'a [F],
+    non_crossing: bool,
+}
+
+impl<'a, F: Float> QuantileRegression<'a, F> {
+    /// Creates a regression for the given quantiles
+    ///
+    /// Returns `InvalidParameter` if `quantiles` is empty, a quantile is not
+    /// strictly between 0 and 1, or the quantiles are not strictly
+    /// increasing.
+    pub fn new(quantiles: &'a [F]) -> Result<Self, LinRegError> {
+        if quantiles.is_empty() {
+            return Err(LinRegError::InvalidParameter);
+        }
+        for &tau in quantiles {
+            check_quantile(tau)?;
+        }
+        if quantiles.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        Ok(QuantileRegression {
+            quantiles,
+            non_crossing: false,
+        })
+    }
+
+    /// Sets whether crossing lines are rearranged, off by default
+    pub fn with_non_crossing(mut self, non_crossing: bool) -> Self {
+        self.non_crossing = non_crossing;
+        self
+    }
+
+    /// Fits the lines to the points in two slices
+    ///
+    /// `lines` must hold a slot for every quantile and receives the
+    /// `(slope, intercept)` of each line, in the order of the quantiles.
+    /// `scratch` must hold at least `xs.len()` pairs. Returns an error if
+    ///
+    /// * `xs` and `ys` differ in length (`LengthMismatch`)
+    /// * `xs` and `ys` are empty (`EmptyInput`)
+    /// * `lines` or `scratch` is too short (`InvalidParameter`)
+    /// * all x values are identical (`ConstantX`)
+    /// * a value, a slope or an intercept is NaN or infinite (`NonFinite`)
+    pub fn fit<X, Y>(
+        &self,
+        xs: &[X],
+        ys: &[Y],
+        lines: &mut [(F, F)],
+        scratch: &mut [(F, F)],
+    ) -> Result<(), LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        if xs.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        self.fit_points(
+            |i| (xs[i].clone().into(), ys[i].clone().into()),
+            xs.len(),
+            lines,
+            scratch,
+        )
+    }
+
+    /// Fits the lines to a slice of tuples
+    ///
+    /// The tuple counterpart of [`fit`](#method.fit).
+    pub fn fit_of<X, Y>(
+        &self,
+        xys: &[(X, Y)],
+        lines: &mut [(F, F)],
+        scratch: &mut [(F, F)],
+    ) -> Result<(), LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        self.fit_points(
+            |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+            xys.len(),
+            lines,
+            scratch,
+        )
+    }
+
+    fn fit_points<G>(
+        &self,
+        point: G,
+        n: usize,
+        lines: &mut [(F, F)],
+        scratch: &mut [(F, F)],
+    ) -> Result<(), LinRegError>
+    where
+        G: Fn(usize) -> (F, F),
+    {
+        if lines.len() < self.quantiles.len() {
+            return Err(LinRegError::InvalidParameter);
+        }
+        let lines = &mut lines[..self.quantiles.len()];
+
+        for (line, &tau) in lines.iter_mut().zip(self.quantiles) {
+            *line = fit_quantile(&point, n, tau, scratch)?;
+        }
+
+        if self.non_crossing {
+            let (x_min, x_max) = (0..n).fold((F::infinity(), F::neg_infinity()), |(lo, hi), i| {
+                let x = point(i).0;
+                (lo.min(x), hi.max(x))
+            });
+            rearrange(lines, x_min, x_max);
+        }
+
+        Ok(())
+    }
+}
+
+/// Sorts the values of the lines at `x_min` and at `x_max` and redraws the
+/// lines through them, unless they are sorted already
+fn rearrange<F: Float>(lines: &mut [(F, F)], x_min: F, x_max: F) {
+    let at = |&(slope, intercept): &(F, F), x: F| intercept + slope * x;
+    let sorted = lines.windows(2).all(|pair| {
+        at(&pair[0], x_min) <= at(&pair[1], x_min) && at(&pair[0], x_max) <= at(&pair[1], x_max)
+    });
+    if sorted {
+        return;
+    }
+
+    // replace every line by its two end values, then sort both by insertion
+    for line in lines.iter_mut() {
+        *line = (at(line, x_min), at(line, x_max));
+    }
+    for i in 1..lines.len() {
+        let mut j = i;
+        while j > 0 && lines[j].0 < lines[j - 1].0 {
+            let (a, b) = (lines[j].0, lines[j - 1].0);
+            lines[j].0 = b;
+            lines[j - 1].0 = a;
+            j -= 1;
+        }
+        let mut j = i;
+        while j > 0 && lines[j].1 < lines[j - 1].1 {
+            let (a, b) = (lines[j].1, lines[j - 1].1);
+            lines[j].1 = b;
+            lines[j - 1].1 = a;
+            j -= 1;
+        }
+    }
+
+    for line in lines.iter_mut() {
+        let (low, high) = *line;
+        let slope = (high - low) / (x_max - x_min);
+        *line = (slope, low - slope * x_min);
+    }
+}
+
+#[test]
+fn test_quantile_regression_shares() {
+    // a share of about tau of the points lies below each line
+    let mut rng = SplitMix64::new(11);
+    let mut xys = [(0.0, 0.0); 200];
+    for (i, p) in xys.iter_mut().enumerate() {
+        let x = i as f64 / 10.0;
+        *p = (x, 5.0 + x + x * uniform(&mut rng));
+    }
+
+    let mut scratch = [(0.0, 0.0); 200];
+    for &tau in &[0.1, 0.25, 0.5, 0.75, 0.95] {
+        let (slope, intercept): (f64, f64) =
+            quantile_regression_of(&xys, tau, &mut scratch).unwrap();
+        assert!((slope - (1.0 + tau)).abs() < 0.1);
+        assert!((intercept - 5.0).abs() < 0.3);
+
+        let below = xys
+            .iter()
+            .filter(|&&(x, y)| y < intercept + slope * x - 1e-9)
+            .count();
+        let on = xys
+            .iter()
+            .filter(|&&(x, y)| (y - intercept - slope * x).abs() <= 1e-9)
+            .count();
+        assert!(below as f64 <= tau * 200.0 && tau * 200.0 <= (below + on) as f64);
+    }
+}
+
+#[test]
+fn test_quantile_regression_median() {
+    let xs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0];
+    let ys = [3.0, 5.0, 7.0, 40.0, 11.0, 13.0, -20.0];
+
+    let mut scratch = [(0.0, 0.0); 7];
+    assert_eq!(
+        ::lad_regression::<f64, f64, f64>(&xs, &ys, &mut scratch),
+        quantile_regression(&xs, &ys, 0.5, &mut scratch)
+    );
+}
+
+#[test]
+fn test_quantile_regression_non_crossing() {
+    // the 0.7 line starts above the 0.8 one
+    let xys = [
+        (0.0, 8.0),
+        (1.0, 11.0),
+        (2.0, 6.0),
+        (3.0, 14.0),
+        (4.0, 12.0),
+        (5.0, 0.0),
+    ];
+    let quantiles = [0.7, 0.8];
+    let mut lines = [(0.0, 0.0); 2];
+    let mut scratch = [(0.0, 0.0); 6];
+
+    let regression = QuantileRegression::new(&quantiles).unwrap();
+    regression.fit_of(&xys, &mut lines, &mut scratch).unwrap();
+    let at = |(slope, intercept): (f64, f64), x: f64| intercept + slope * x;
+    assert!(at(lines[0], 0.0) > at(lines[1], 0.0) || at(lines[0], 5.0) > at(lines[1], 5.0));
+    let crossing = lines;
+
+    regression
+        .with_non_crossing(true)
+        .fit_of(&xys, &mut lines, &mut scratch)
+        .unwrap();
+    for &x in &[0.0, 5.0] {
+        assert!(at(lines[0], x) <= at(lines[1], x));
+        let low = at(crossing[0], x).min(at(crossing[1], x));
+        let high = at(crossing[0], x).max(at(crossing[1], x));
+        assert!((at(lines[0], x) - low).abs() < 1e-12);
+        assert!((at(lines[1], x) - high).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_quantile_regression_errors() {
+    let mut lines = [(0.0, 0.0); 1];
+    let mut scratch = [(0.0, 0.0); 3];
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        quantile_regression(&[1.0, 2.0], &[1.0, 2.0], 1.0, &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        quantile_regression(&[1.0, 2.0], &[1.0, 2.0], f64::NAN, &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        quantile_regression(&[1.0, 2.0], &[1.0], 0.5, &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        quantile_regression_of(&[(1.0, 1.0), (1.0, 2.0)], 0.5, &mut scratch)
+    );
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        QuantileRegression::<f64>::new(&[])
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        QuantileRegression::new(&[0.9, 0.5])
+    );
+    let quantiles = [0.25, 0.75];
+    let regression = QuantileRegression::new(&quantiles).unwrap();
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        regression.fit(&[1.0, 2.0], &[1.0, 2.0], &mut lines, &mut scratch)
+    );
+}
@@ -0,0 +1,386 @@
+use num_traits::Float;
+
+use dist::constant;
+use error::LinRegError;
+use try_lin_reg;
+
+/// Source of random numbers for randomized estimators
+///
+/// Implement this for the generator of your choice, or use the seeded
+/// [`SplitMix64`](struct.SplitMix64.html) for reproducible results without
+/// libstd.
+pub trait RandomSource {
+    /// Returns the next uniformly distributed 64-bit value
+    fn next_u64(&mut self) -> u64;
+}
+
+/// Small, fast pseudo-random generator with a 64-bit seed
+///
+/// Produces the SplitMix64 sequence of Steele, Lea and Flood. It is not
+/// cryptographically secure, but good enough for sampling.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    /// Creates a generator; equal seeds produce equal sequences
+    pub fn new(seed: u64) -> Self {
+        SplitMix64 { state: seed }
+    }
+}
+
+impl RandomSource for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// Uniformly distributed value in `[0, 1)`, for test data
+#[cfg(test)]
+pub(crate) fn uniform(rng: &mut SplitMix64) -> f64 {
+    (rng.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Uniformly distributed index below `n`
+pub(crate) fn random_index<R: RandomSource>(rng: &mut R, n: usize) -> usize {
+    ((u128::from(rng.next_u64()) * n as u128) >> 64) as usize
+}
+
+/// RANSAC line fitter for data with many outliers
+///
+/// Repeatedly draws two points at random, takes the line through them and
+/// counts the points within `threshold` of it (measured along y). The line
+/// with the largest such consensus set is refined by a least squares fit
+/// over that set. Stops after `max_iterations` samples, or earlier once the
+/// consensus set is large enough that another sample is unlikely to find a
+/// better line.
+///
+/// ```rust
+/// use linreg::{Ransac, SplitMix64};
+///
+/// let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0];
+/// let ys = [1.0, 3.0, -7.0, 7.0, 40.0, 11.0, 2.0, 15.0, 0.5, 19.0];
+///
+/// let ransac = Ransac::new(0.1f64).unwrap();
+/// let mut inliers = [false; 10];
+/// let fit = ransac
+///     .fit(&xs, &ys, &mut SplitMix64::new(42), &mut inliers)
+///     .unwrap();
+///
+/// assert!((fit.slope - 2.0).abs() < 1e-12);
+/// assert!((fit.intercept - 1.0).abs() < 1e-12);
+/// assert_eq!(6, fit.inliers);
+/// assert!(!inliers[4]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ransac<F> {
+    threshold: F,
+    max_iterations: usize,
+    confidence: F,
+}
+
+/// Line found by [`Ransac`](struct.Ransac.html)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RansacResult<F> {
+    /// Slope of the least squares line through the consensus set
+    pub slope: F,
+    /// Intercept of the least squares line through the consensus set
+    pub intercept: F,
+    /// Number of points in the consensus set
+    pub inliers: usize,
+    /// Number of samples drawn
+    pub iterations: usize,
+}
+
+impl<F: Float> Ransac<F> {
+    /// Creates a fitter that accepts points within `threshold` of a line
+    ///
+    /// `threshold` must be finite and non-negative; returns
+    /// `InvalidParameter` otherwise. By default, at most 1000 samples are
+    /// drawn and sampling stops once a better line is less than 1% likely.
+    pub fn new(threshold: F) -> Result<Self, LinRegError> {
+        if !threshold.is_finite() || threshold < F::zero() {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        Ok(Ransac {
+            threshold,
+            max_iterations: 1000,
+            confidence: constant(0.99),
+        })
+    }
+
+    /// Sets the maximum number of samples
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Sets the probability of having drawn an all-inlier sample at which
+    /// sampling stops early
+    ///
+    /// A `confidence` of one disables early stopping. Values outside
+    /// `(0, 1]` make [`fit`](#method.fit) fail with `InvalidParameter`.
+    pub fn with_confidence(mut self, confidence: F) -> Self {
+        self.confidence = confidence;
+        self
+    }
+
+    /// Fits a line to the points in two slices
+    ///
+    /// Sets `inliers[i]` to whether point `i` belongs to the consensus set;
+    /// `inliers` must hold at least `xs.len()` values. Returns an error if
+    ///
+    /// * `xs` and `ys` differ in length (`LengthMismatch`)
+    /// * `xs` and `ys` are empty (`EmptyInput`)
+    /// * there is only one point (`TooFewPoints`)
+    /// * `inliers` is too short or the confidence is invalid
+    ///   (`InvalidParameter`)
+    /// * no sample had two different x values (`ConstantX`)
+    /// * the refined line is NaN or infinite (`NonFinite`)
+    pub fn fit<X, Y, R>(
+        &self,
+        xs: &[X],
+        ys: &[Y],
+        rng: &mut R,
+        inliers: &mut [bool],
+    ) -> Result<RansacResult<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+        R: RandomSource,
+    {
+        if xs.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        self.fit_points(
+            |i| (xs[i].clone().into(), ys[i].clone().into()),
+            xs.len(),
+            rng,
+            inliers,
+        )
+    }
+
+    /// Fits a line to a slice of tuples
+    ///
+    /// The tuple counterpart of [`fit`](#method.fit).
+    pub fn fit_of<X, Y, R>(
+        &self,
+        xys: &[(X, Y)],
+        rng: &mut R,
+        inliers: &mut [bool],
+    ) -> Result<RansacResult<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+        R: RandomSource,
+    {
+        self.fit_points(
+            |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+            xys.len(),
+            rng,
+            inliers,
+        )
+    }
+
+    fn fit_points<G, R>(
+        &self,
+        point: G,
+        n: usize,
+        rng: &mut R,
+        inliers: &mut [bool],
+    ) -> Result<RansacResult<F>, LinRegError>
+    where
+        G: Fn(usize) -> (F, F),
+        R: RandomSource,
+    {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+        if n == 1 {
+            return Err(LinRegError::TooFewPoints);
+        }
+        if inliers.len() < n
+            || self.confidence.is_nan()
+            || self.confidence <= F::zero()
+            || self.confidence > F::one()
+        {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+        let within = |slope: F, intercept: F, (x, y): (F, F)| {
+            (y - (intercept + slope * x)).abs() <= self.threshold
+        };
+
+        let mut best: Option<(F, F)> = None;
+        let mut best_count = 0;
+        let mut needed = self.max_iterations;
+        let mut iterations = 0;
+
+        while iterations < needed {
+            iterations += 1;
+
+            let i = random_index(rng, n);
+            // a second index that differs from the first
+            let j = (i + 1 + random_index(rng, n - 1)) % n;
+            let (x_i, y_i) = point(i);
+            let (x_j, y_j) = point(j);
+            if x_i == x_j {
+                continue;
+            }
+
+            let slope = (y_j - y_i) / (x_j - x_i);
+            let intercept = y_i - slope * x_i;
+            if !slope.is_finite() || !intercept.is_finite() {
+                continue;
+            }
+
+            let count = (0..n)
+                .filter(|&k| within(slope, intercept, point(k)))
+                .count();
+            if count <= best_count {
+                continue;
+            }
+            best = Some((slope, intercept));
+            best_count = count;
+
+            // samples needed to draw two inliers at least once with the
+            // requested confidence, given the inlier ratio seen so far
+            let ratio = F::from(count).ok_or(LinRegError::CountNotRepresentable)? / n_f;
+            let miss = F::one() - ratio * ratio;
+            if miss <= F::zero() {
+                break;
+            }
+            let samples = (F::one() - self.confidence).ln() / miss.ln();
+            if let Some(samples) = samples.ceil().to_usize() {
+                needed = needed.min(samples);
+            }
+        }
+
+        let (slope, intercept) = best.ok_or(LinRegError::ConstantX)?;
+
+        let mut x_sum = F::zero();
+        let mut y_sum = F::zero();
+        for (k, inlier) in inliers[..n].iter_mut().enumerate() {
+            let p = point(k);
+            *inlier = within(slope, intercept, p);
+            if *inlier {
+                x_sum = x_sum + p.0;
+                y_sum = y_sum + p.1;
+            }
+        }
+
+        let count = F::from(best_count).ok_or(LinRegError::CountNotRepresentable)?;
+        let consensus = (0..n).filter(|&k| inliers[k]).map(&point);
+        let (slope, intercept) = try_lin_reg(consensus, x_sum / count, y_sum / count)?;
+
+        Ok(RansacResult {
+            slope,
+            intercept,
+            inliers: best_count,
+            iterations,
+        })
+    }
+}
+
+#[test]
+fn test_ransac_contaminated_line() {
+    let mut rng = SplitMix64::new(7);
+
+    // 40 points on y = 0.5 x - 3 with small noise, 60 outliers
+    let mut xys = [(0.0f64, 0.0f64); 100];
+    for (i, p) in xys.iter_mut().enumerate() {
+        let x = i as f64 * 0.1;
+        *p = if i % 5 < 2 {
+            let noise = (i % 7) as f64 * 0.01 - 0.03;
+            (x, 0.5 * x - 3.0 + noise)
+        } else {
+            (x, 20.0 * uniform(&mut rng) - 10.0)
+        };
+    }
+
+    let mut inliers = [false; 100];
+    let fit = Ransac::new(0.2)
+        .unwrap()
+        .fit_of(&xys, &mut SplitMix64::new(1), &mut inliers)
+        .unwrap();
+
+    assert!((fit.slope - 0.5).abs() < 1e-2);
+    assert!((fit.intercept + 3.0).abs() < 5e-2);
+    assert!(fit.inliers >= 40);
+    for (i, &inlier) in inliers.iter().enumerate() {
+        if i % 5 < 2 {
+            assert!(inlier);
+        }
+    }
+    assert!(fit.iterations < 1000);
+
+    // the same seed gives the same result
+    let mut again = [false; 100];
+    let repeated = Ransac::new(0.2)
+        .unwrap()
+        .fit_of(&xys, &mut SplitMix64::new(1), &mut again)
+        .unwrap();
+    assert_eq!(fit, repeated);
+    assert_eq!(&inliers[..], &again[..]);
+}
+
+#[test]
+fn test_ransac_max_iterations() {
+    let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+    let ys = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+
+    let mut inliers = [false; 6];
+    let fit = Ransac::new(0.0)
+        .unwrap()
+        .with_max_iterations(1)
+        .with_confidence(1.0)
+        .fit(&xs, &ys, &mut SplitMix64::new(3), &mut inliers)
+        .unwrap();
+
+    assert_eq!(1, fit.iterations);
+    assert_eq!(6, fit.inliers);
+    assert!((fit.slope - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_ransac_errors() {
+    let ransac = Ransac::new(1.0).unwrap();
+    let mut rng = SplitMix64::new(0);
+    let mut inliers = [false; 3];
+
+    assert_eq!(Err(LinRegError::InvalidParameter), Ransac::new(-1.0));
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        ransac.fit(&[1.0, 2.0], &[1.0], &mut rng, &mut inliers)
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        ransac.fit_of::<f64, f64, _>(&[], &mut rng, &mut inliers)
+    );
+    assert_eq!(
+        Err(LinRegError::TooFewPoints),
+        ransac.fit(&[1.0], &[1.0], &mut rng, &mut inliers)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ransac.fit(&[1.0; 4], &[1.0; 4], &mut rng, &mut inliers)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ransac
+            .with_confidence(0.0)
+            .fit(&[1.0, 2.0], &[1.0, 2.0], &mut rng, &mut inliers)
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        ransac.fit(&[2.0, 2.0, 2.0], &[1.0, 2.0, 3.0], &mut rng, &mut inliers)
+    );
+}
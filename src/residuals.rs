@@ -0,0 +1,196 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+use result::RegressionResult;
+
+/// Summary statistics of the residuals of a regression line
+///
+/// Residuals are `y - prediction`, so points above the line have positive
+/// residuals.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ResidualSummary<F> {
+    /// Smallest residual
+    pub min: F,
+    /// Largest residual
+    pub max: F,
+    /// Mean residual
+    pub mean: F,
+    /// Root mean squared residual
+    pub rmse: F,
+    /// Mean absolute residual
+    pub mae: F,
+    /// Largest absolute residual
+    pub max_abs: F,
+    /// Index of the first residual with the largest absolute value
+    pub max_abs_index: usize,
+}
+
+impl<F: Float> ResidualSummary<F> {
+    /// Summarizes residuals in a single pass
+    ///
+    /// Returns an error if
+    ///
+    /// * `residuals` is empty (`EmptyInput`)
+    /// * a residual is NaN or infinite (`NonFinite`)
+    /// * the number of residuals cannot be represented as an `F`
+    pub fn from_residuals<I>(residuals: I) -> Result<ResidualSummary<F>, LinRegError>
+    where
+        I: IntoIterator<Item = F>,
+    {
+        let mut summary = ResidualSummary {
+            min: F::infinity(),
+            max: F::neg_infinity(),
+            mean: F::zero(),
+            rmse: F::zero(),
+            mae: F::zero(),
+            max_abs: F::zero(),
+            max_abs_index: 0,
+        };
+
+        // running sums, turned into means at the end
+        let mut sum = F::zero();
+        let mut sum_sq = F::zero();
+        let mut sum_abs = F::zero();
+        let mut n = 0;
+
+        for (i, r) in residuals.into_iter().enumerate() {
+            if !r.is_finite() {
+                return Err(LinRegError::NonFinite);
+            }
+
+            summary.min = summary.min.min(r);
+            summary.max = summary.max.max(r);
+            if i == 0 || r.abs() > summary.max_abs {
+                summary.max_abs = r.abs();
+                summary.max_abs_index = i;
+            }
+
+            sum = sum + r;
+            sum_sq = sum_sq + r * r;
+            sum_abs = sum_abs + r.abs();
+            n += 1;
+        }
+
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+
+        let n = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+        summary.mean = sum / n;
+        summary.rmse = (sum_sq / n).sqrt();
+        summary.mae = sum_abs / n;
+
+        Ok(summary)
+    }
+}
+
+impl<F: Float> RegressionResult<F> {
+    /// Residuals of the regression line for two slices
+    ///
+    /// The residuals are calculated lazily, so no memory is allocated.
+    /// Returns an error if `xs` and `ys` differ in length.
+    pub fn residuals<'a, X, Y>(
+        &self,
+        xs: &'a [X],
+        ys: &'a [Y],
+    ) -> Result<impl Iterator<Item = F> + 'a, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+        F: 'a,
+    {
+        if xs.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        let (slope, intercept) = (self.slope, self.intercept);
+        Ok(xs
+            .iter()
+            .zip(ys.iter())
+            .map(move |(x, y)| y.clone().into() - (intercept + slope * x.clone().into())))
+    }
+
+    /// Residuals of the regression line for tuples
+    ///
+    /// The residuals are calculated lazily, so no memory is allocated.
+    pub fn residuals_of<'a, X, Y>(&self, xys: &'a [(X, Y)]) -> impl Iterator<Item = F> + 'a
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+        F: 'a,
+    {
+        let (slope, intercept) = (self.slope, self.intercept);
+        xys.iter()
+            .map(move |(x, y)| y.clone().into() - (intercept + slope * x.clone().into()))
+    }
+
+    /// Summary of the residuals for two slices
+    ///
+    /// See [`ResidualSummary::from_residuals`](struct.ResidualSummary.html#method.from_residuals)
+    /// for possible errors. Also fails if `xs` and `ys` differ in length.
+    pub fn residual_summary<X, Y>(
+        &self,
+        xs: &[X],
+        ys: &[Y],
+    ) -> Result<ResidualSummary<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        ResidualSummary::from_residuals(self.residuals(xs, ys)?)
+    }
+
+    /// Summary of the residuals for tuples
+    ///
+    /// See [`ResidualSummary::from_residuals`](struct.ResidualSummary.html#method.from_residuals)
+    /// for possible errors.
+    pub fn residual_summary_of<X, Y>(
+        &self,
+        xys: &[(X, Y)],
+    ) -> Result<ResidualSummary<F>, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        ResidualSummary::from_residuals(self.residuals_of(xys))
+    }
+}
+
+#[test]
+fn test_residuals() {
+    let r = RegressionResult::from_moments(5, 3.0, 4.0, 10.0, 6.0, 6.0).unwrap();
+    let xs: Vec<u8> = vec![1, 2, 3, 4, 5];
+    let ys: Vec<u8> = vec![2, 4, 5, 4, 5];
+
+    let expected = [-0.8, 0.6, 1.0, -0.6, -0.2];
+    let residuals: Vec<f64> = r.residuals(&xs, &ys).unwrap().collect();
+    for (r, e) in residuals.iter().zip(expected.iter()) {
+        assert!((r - e).abs() < 1e-12);
+    }
+
+    let tuples: Vec<(u8, u8)> = xs.iter().cloned().zip(ys.iter().cloned()).collect();
+    assert_eq!(residuals, r.residuals_of(&tuples).collect::<Vec<f64>>());
+
+    assert!(r.residuals(&xs, &ys[1..]).is_err());
+}
+
+#[test]
+fn test_residual_summary() {
+    let r = RegressionResult::from_moments(5, 3.0, 4.0, 10.0, 6.0, 6.0).unwrap();
+    let tuples: Vec<(f64, f64)> = vec![(1.0, 2.0), (2.0, 4.0), (3.0, 5.0), (4.0, 4.0), (5.0, 5.0)];
+
+    let s = r.residual_summary_of(&tuples).unwrap();
+    assert!((s.min + 0.8).abs() < 1e-12);
+    assert!((s.max - 1.0).abs() < 1e-12);
+    assert!(s.mean.abs() < 1e-12);
+    assert!((s.rmse - (2.4_f64 / 5.0).sqrt()).abs() < 1e-12);
+    assert!((s.mae - 0.64).abs() < 1e-12);
+    assert!((s.max_abs - 1.0).abs() < 1e-12);
+    assert_eq!(2, s.max_abs_index);
+
+    let empty: Vec<(f64, f64)> = vec![];
+    assert_eq!(Err(LinRegError::EmptyInput), r.residual_summary_of(&empty));
+}
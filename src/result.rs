@@ -0,0 +1,465 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use dist::{constant, students_t_quantile, students_t_two_sided_p};
+use error::LinRegError;
+
+/// A fitted regression line together with goodness-of-fit statistics
+///
+/// Sums of squares are taken around the means, i.e. `sxx` is
+/// `SUM (x-mean(x))^2`. For lines forced through a fixed point, such as the
+/// origin, they are taken around that point instead and `r_squared` is
+/// uncentered. Statistics that need residual degrees of freedom are `NaN`
+/// when `df` is zero, e.g. for an ordinary fit of two points.
+///
+/// Standard errors, t-statistics and p-values assume independent, normally
+/// distributed errors with constant variance. The p-values are two-sided and
+/// test against a true value of zero.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RegressionResult<F> {
+    /// Slope of the regression line
+    pub slope: F,
+    /// Intercept of the regression line
+    pub intercept: F,
+    /// Number of data points
+    pub n: usize,
+    /// Residual degrees of freedom, the number of data points minus the
+    /// number of estimated parameters
+    pub df: usize,
+    /// Mean of the x values
+    pub x_mean: F,
+    /// Mean of the y values
+    pub y_mean: F,
+    /// SUM (x-mean(x))^2
+    pub sxx: F,
+    /// SUM (y-mean(y))^2
+    pub syy: F,
+    /// SUM (x-mean(x)) (y-mean(y))
+    pub sxy: F,
+    /// Sum of squared residuals
+    pub sse: F,
+    /// Coefficient of determination, `NaN` if all y values are identical
+    pub r_squared: F,
+    /// R² adjusted for the number of parameters
+    pub adjusted_r_squared: F,
+    /// Estimated standard deviation of the residuals
+    pub residual_std_error: F,
+    /// Standard error of the slope
+    pub slope_std_error: F,
+    /// Standard error of the intercept
+    pub intercept_std_error: F,
+    /// t-statistic of the slope
+    pub slope_t_value: F,
+    /// t-statistic of the intercept
+    pub intercept_t_value: F,
+    /// p-value of the slope
+    pub slope_p_value: F,
+    /// p-value of the intercept
+    pub intercept_p_value: F,
+    // x at which the fitted value does not depend on the estimated slope
+    pivot: F,
+    // variance of the fitted value at `pivot`, in units of the residual variance
+    pivot_leverage: F,
+    // variance of the slope, in units of the residual variance
+    slope_leverage: F,
+}
+
+/// How the parameters of a line were estimated, which determines its statistics
+struct Estimate<F> {
+    slope: F,
+    intercept: F,
+    sse: F,
+    r_squared: F,
+    // number of estimated parameters
+    parameters: usize,
+    // whether `r_squared` compares against the mean of y
+    centered: bool,
+    // variances of the estimates in units of the residual variance, zero if
+    // a parameter is fixed
+    slope_variance: F,
+    intercept_variance: F,
+    pivot: F,
+    pivot_leverage: F,
+}
+
+/// Confidence intervals for the parameters of a regression line
+///
+/// Each interval is given as `[lower, upper]`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConfidenceIntervals<F> {
+    /// Confidence interval of the slope
+    pub slope: [F; 2],
+    /// Confidence interval of the intercept
+    pub intercept: [F; 2],
+}
+
+impl<F: Float> RegressionResult<F> {
+    /// Builds the result from the means and sums of squares of `n` data points
+    pub(crate) fn from_moments(
+        n: usize,
+        x_mean: F,
+        y_mean: F,
+        sxx: F,
+        syy: F,
+        sxy: F,
+    ) -> Result<RegressionResult<F>, LinRegError> {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+
+        if sxx == F::zero() {
+            return Err(LinRegError::ConstantX);
+        }
+
+        let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+
+        let slope = sxy / sxx;
+        let intercept = y_mean - slope * x_mean;
+
+        // the explained sum of squares can exceed syy by rounding errors
+        let sse = (syy - slope * sxy).max(F::zero());
+
+        let estimate = Estimate {
+            slope,
+            intercept,
+            sse,
+            r_squared: slope * sxy / syy,
+            parameters: 2,
+            centered: true,
+            slope_variance: F::one() / sxx,
+            intercept_variance: F::one() / n_f + x_mean * x_mean / sxx,
+            pivot: x_mean,
+            pivot_leverage: F::one() / n_f,
+        };
+
+        RegressionResult::finish(n, x_mean, y_mean, sxx, syy, sxy, estimate)
+    }
+
+    /// Builds the result for a line forced through `(x0, y0)`
+    ///
+    /// The sums of squares are taken around `(x0, y0)` instead of the means.
+    pub(crate) fn through_point(
+        n: usize,
+        x_mean: F,
+        y_mean: F,
+        (x0, y0): (F, F),
+        sxx: F,
+        syy: F,
+        sxy: F,
+    ) -> Result<RegressionResult<F>, LinRegError> {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+
+        if sxx == F::zero() {
+            return Err(LinRegError::ConstantX);
+        }
+
+        let slope = sxy / sxx;
+        let sse = (syy - slope * sxy).max(F::zero());
+
+        let estimate = Estimate {
+            slope,
+            intercept: y0 - slope * x0,
+            sse,
+            r_squared: slope * sxy / syy,
+            parameters: 1,
+            centered: false,
+            slope_variance: F::one() / sxx,
+            intercept_variance: x0 * x0 / sxx,
+            pivot: x0,
+            pivot_leverage: F::zero(),
+        };
+
+        RegressionResult::finish(n, x_mean, y_mean, sxx, syy, sxy, estimate)
+    }
+
+    /// Builds the result for a line with a known slope
+    ///
+    /// The sums of squares are taken around the means. Only the intercept is
+    /// estimated, so `r_squared` can be negative if the slope does not suit
+    /// the data.
+    pub(crate) fn with_fixed_slope(
+        n: usize,
+        x_mean: F,
+        y_mean: F,
+        slope: F,
+        sxx: F,
+        syy: F,
+        sxy: F,
+    ) -> Result<RegressionResult<F>, LinRegError> {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+
+        let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+        let two = constant::<F>(2.0);
+        let sse = (syy - two * slope * sxy + slope * slope * sxx).max(F::zero());
+
+        let estimate = Estimate {
+            slope,
+            intercept: y_mean - slope * x_mean,
+            sse,
+            r_squared: F::one() - sse / syy,
+            parameters: 1,
+            centered: true,
+            slope_variance: F::zero(),
+            intercept_variance: F::one() / n_f,
+            pivot: x_mean,
+            pivot_leverage: F::one() / n_f,
+        };
+
+        RegressionResult::finish(n, x_mean, y_mean, sxx, syy, sxy, estimate)
+    }
+
+    /// Calculates the statistics shared by all kinds of fits
+    fn finish(
+        n: usize,
+        x_mean: F,
+        y_mean: F,
+        sxx: F,
+        syy: F,
+        sxy: F,
+        estimate: Estimate<F>,
+    ) -> Result<RegressionResult<F>, LinRegError> {
+        let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+        let Estimate {
+            slope,
+            intercept,
+            sse,
+            r_squared,
+            parameters,
+            centered,
+            slope_variance,
+            intercept_variance,
+            pivot,
+            pivot_leverage,
+        } = estimate;
+
+        if !slope.is_finite() || !intercept.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+
+        let mut result = RegressionResult {
+            slope,
+            intercept,
+            n,
+            df: n.saturating_sub(parameters),
+            x_mean,
+            y_mean,
+            sxx,
+            syy,
+            sxy,
+            sse,
+            r_squared,
+            adjusted_r_squared: F::nan(),
+            residual_std_error: F::nan(),
+            slope_std_error: F::nan(),
+            intercept_std_error: F::nan(),
+            slope_t_value: F::nan(),
+            intercept_t_value: F::nan(),
+            slope_p_value: F::nan(),
+            intercept_p_value: F::nan(),
+            pivot,
+            pivot_leverage,
+            slope_leverage: slope_variance,
+        };
+
+        if result.df > 0 {
+            let one = F::one();
+            let df = F::from(result.df).ok_or(LinRegError::CountNotRepresentable)?;
+            let s = (sse / df).sqrt();
+
+            // a centered R² already accounts for one parameter, the mean
+            let total_df = if centered { n_f - one } else { n_f };
+            result.adjusted_r_squared = one - (one - r_squared) * total_df / df;
+            result.residual_std_error = s;
+
+            result.slope_std_error = s * slope_variance.sqrt();
+            result.intercept_std_error = s * intercept_variance.sqrt();
+
+            // fixed parameters have no meaningful test statistic
+            if slope_variance > F::zero() {
+                result.slope_t_value = slope / result.slope_std_error;
+                result.slope_p_value = students_t_two_sided_p(result.slope_t_value, df);
+            }
+            if intercept_variance > F::zero() {
+                result.intercept_t_value = intercept / result.intercept_std_error;
+                result.intercept_p_value = students_t_two_sided_p(result.intercept_t_value, df);
+            }
+        }
+
+        Ok(result)
+    }
+
+    /// Confidence intervals for slope and intercept
+    ///
+    /// `level` is the confidence level, e.g. `0.95` for 95% intervals. The
+    /// intervals are based on Student's t-distribution with `df` degrees of
+    /// freedom.
+    ///
+    /// Returns an error if
+    ///
+    /// * `level` is not strictly between zero and one (`InvalidParameter`)
+    /// * there are no residual degrees of freedom (`TooFewPoints`)
+    pub fn confidence_intervals(&self, level: F) -> Result<ConfidenceIntervals<F>, LinRegError> {
+        let q = self.t_quantile(level)?;
+
+        let slope = q * self.slope_std_error;
+        let intercept = q * self.intercept_std_error;
+
+        Ok(ConfidenceIntervals {
+            slope: [self.slope - slope, self.slope + slope],
+            intercept: [self.intercept - intercept, self.intercept + intercept],
+        })
+    }
+
+    /// Evaluates the regression line at `x`
+    pub fn predict(&self, x: F) -> F {
+        self.intercept + self.slope * x
+    }
+
+    /// Evaluates the regression line at each of the `xs`
+    ///
+    /// The predictions are calculated lazily, so no memory is allocated.
+    pub fn predict_many<'a, X>(&self, xs: &'a [X]) -> impl Iterator<Item = F> + 'a
+    where
+        X: Clone + Into<F>,
+        F: 'a,
+    {
+        let (slope, intercept) = (self.slope, self.intercept);
+        xs.iter().map(move |x| intercept + slope * x.clone().into())
+    }
+
+    /// Confidence interval of the mean response at `x`
+    ///
+    /// Returns `[lower, upper]` for the value of the true regression line at
+    /// `x`. Fails for the same reasons as
+    /// [`confidence_intervals`](#method.confidence_intervals).
+    pub fn confidence_interval(&self, x: F, level: F) -> Result<[F; 2], LinRegError> {
+        let q = self.t_quantile(level)?;
+        let half_width = q * self.residual_std_error * self.leverage(x).sqrt();
+
+        let y = self.predict(x);
+        Ok([y - half_width, y + half_width])
+    }
+
+    /// Prediction interval of a new observation at `x`
+    ///
+    /// Returns `[lower, upper]` for a single new y value measured at `x`,
+    /// which is wider than the [`confidence_interval`](#method.confidence_interval)
+    /// because it includes the scatter around the line.
+    pub fn prediction_interval(&self, x: F, level: F) -> Result<[F; 2], LinRegError> {
+        let q = self.t_quantile(level)?;
+        let half_width = q * self.residual_std_error * (F::one() + self.leverage(x)).sqrt();
+
+        let y = self.predict(x);
+        Ok([y - half_width, y + half_width])
+    }
+
+    /// Variance of the fitted value at `x`, in units of the residual variance
+    fn leverage(&self, x: F) -> F {
+        let dx = x - self.pivot;
+        self.pivot_leverage + dx * dx * self.slope_leverage
+    }
+
+    /// Two-sided t-quantile for the confidence level `level`
+    fn t_quantile(&self, level: F) -> Result<F, LinRegError> {
+        if !(level > F::zero() && level < F::one()) {
+            return Err(LinRegError::InvalidParameter);
+        }
+        if self.df == 0 {
+            return Err(LinRegError::TooFewPoints);
+        }
+
+        let df = F::from(self.df).ok_or(LinRegError::CountNotRepresentable)?;
+        Ok(students_t_quantile((F::one() + level) * constant(0.5), df))
+    }
+}
+
+#[test]
+fn test_goodness_of_fit() {
+    let r = RegressionResult::from_moments(5, 3.0, 4.0, 10.0, 6.0, 6.0).unwrap();
+
+    assert_eq!(0.6, r.slope);
+    assert_eq!(2.2, r.intercept);
+    assert_eq!(3, r.df);
+    assert!((r.sse - 2.4).abs() < 1e-12);
+    assert!((r.r_squared - 0.6).abs() < 1e-12);
+    assert!((r.adjusted_r_squared - 0.466_666_666_666_666_7).abs() < 1e-12);
+    assert!((r.residual_std_error - 0.8_f64.sqrt()).abs() < 1e-12);
+}
+
+#[test]
+fn test_significance() {
+    let r = RegressionResult::from_moments(5, 3.0, 4.0, 10.0, 6.0, 6.0).unwrap();
+
+    assert!((r.slope_std_error - 0.282_842_712_474_619).abs() < 1e-12);
+    assert!((r.intercept_std_error - 0.938_083_151_964_686).abs() < 1e-12);
+    assert!((r.slope_t_value - 2.121_320_343_559_642).abs() < 1e-12);
+    assert!((r.intercept_t_value - 2.345_207_879_911_715).abs() < 1e-12);
+    assert!((r.slope_p_value - 0.124_027_062_657_550_6).abs() < 1e-9);
+    assert!((r.intercept_p_value - 0.100_743_456_085_422_3).abs() < 1e-9);
+}
+
+#[test]
+fn test_two_points() {
+    let r = RegressionResult::from_moments(2, 1.5, 1.5, 0.5, 0.5, 0.5).unwrap();
+
+    assert_eq!(1.0, r.slope);
+    assert_eq!(0.0, r.intercept);
+    assert_eq!(1.0, r.r_squared);
+    assert!(r.adjusted_r_squared.is_nan());
+    assert!(r.residual_std_error.is_nan());
+    assert!(r.slope_std_error.is_nan());
+    assert!(r.slope_p_value.is_nan());
+}
+
+#[test]
+fn test_confidence_intervals() {
+    let r = RegressionResult::from_moments(5, 3.0, 4.0, 10.0, 6.0, 6.0).unwrap();
+    let ci = r.confidence_intervals(0.95).unwrap();
+
+    // t(0.975, 3) = 3.182446305284263
+    assert!((ci.slope[0] - (0.6 - 0.900_131_745_291_430)).abs() < 1e-9);
+    assert!((ci.slope[1] - (0.6 + 0.900_131_745_291_430)).abs() < 1e-9);
+    assert!((ci.intercept[0] - (2.2 - 2.985_399_261_019_431)).abs() < 1e-9);
+    assert!((ci.intercept[1] - (2.2 + 2.985_399_261_019_431)).abs() < 1e-9);
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        r.confidence_intervals(1.0)
+    );
+
+    let r = RegressionResult::from_moments(2, 1.5, 1.5, 0.5, 0.5, 0.5).unwrap();
+    assert_eq!(Err(LinRegError::TooFewPoints), r.confidence_intervals(0.9));
+}
+
+#[test]
+fn test_prediction() {
+    let r = RegressionResult::from_moments(5, 3.0, 4.0, 10.0, 6.0, 6.0).unwrap();
+
+    assert_eq!(2.2, r.predict(0.0));
+    assert!((r.predict(10.0) - 8.2).abs() < 1e-12);
+
+    let xs: Vec<u8> = vec![1, 2, 3];
+    let ys: Vec<f64> = r.predict_many(&xs).collect();
+    assert_eq!(3, ys.len());
+    assert!((ys[2] - 4.0).abs() < 1e-12);
+
+    // at the mean of x, the leverage is 1/n
+    let q = 3.182_446_305_284_263 * 0.8_f64.sqrt();
+    let ci = r.confidence_interval(3.0, 0.95).unwrap();
+    assert!((ci[0] - (4.0 - q * 0.2_f64.sqrt())).abs() < 1e-9);
+    assert!((ci[1] - (4.0 + q * 0.2_f64.sqrt())).abs() < 1e-9);
+
+    let pi = r.prediction_interval(3.0, 0.95).unwrap();
+    assert!((pi[0] - (4.0 - q * 1.2_f64.sqrt())).abs() < 1e-9);
+    assert!((pi[1] - (4.0 + q * 1.2_f64.sqrt())).abs() < 1e-9);
+
+    // the bands widen away from the mean
+    let far = r.confidence_interval(10.0, 0.95).unwrap();
+    assert!(far[1] - far[0] > ci[1] - ci[0]);
+}
@@ -0,0 +1,499 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+use multi::{add_row, check_rank, solve_upper, MultiRegression};
+use stats::{centered_sums, means};
+
+/// Checks a ridge penalty
+fn check_lambda<F: Float>(lambda: F) -> Result<F, LinRegError> {
+    if lambda.is_finite() && lambda >= F::zero() {
+        Ok(lambda)
+    } else {
+        Err(LinRegError::InvalidParameter)
+    }
+}
+
+/// Generalized cross-validation score, `n RSS / (n - df)^2`
+///
+/// Is `NaN` or infinite if `df` is not smaller than `n`.
+fn gcv_score<F: Float>(n: usize, rss: F, effective_df: F) -> Result<F, LinRegError> {
+    let n = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+    let rest = n - effective_df;
+    if rest <= F::zero() {
+        return Ok(F::nan());
+    }
+    Ok(n * rss / (rest * rest))
+}
+
+/// Ridge regression line, chosen by generalized cross-validation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RidgeLine<F> {
+    /// Slope of the shrunk regression line
+    pub slope: F,
+    /// Intercept of the regression line, which is not penalized
+    pub intercept: F,
+    /// Penalty the line was fitted with
+    pub lambda: F,
+    /// Effective number of parameters, between one and two
+    pub effective_df: F,
+    /// Generalized cross-validation score, lower is better
+    pub gcv: F,
+}
+
+/// Slope and intercept of the ridge line from centered sums
+fn ridge_line<F: Float>(
+    x_mean: F,
+    y_mean: F,
+    sxx: F,
+    sxy: F,
+    lambda: F,
+) -> Result<(F, F), LinRegError> {
+    if sxx + lambda == F::zero() {
+        return Err(LinRegError::ConstantX);
+    }
+
+    let slope = sxy / (sxx + lambda);
+    let intercept = y_mean - slope * x_mean;
+
+    if !slope.is_finite() || !intercept.is_finite() {
+        return Err(LinRegError::NonFinite);
+    }
+
+    Ok((slope, intercept))
+}
+
+/// Calculates a ridge regression
+///
+/// Lower-level ridge regression function, the counterpart of
+/// [`lin_reg`](fn.lin_reg.html). Assumes that `x_mean` and `y_mean` have
+/// already been calculated. Minimizes the sum of squared residuals plus
+/// `lambda * slope^2`, which shrinks the slope towards zero; the intercept is
+/// not penalized. A `lambda` of zero gives the ordinary regression line.
+///
+/// Returns an error if
+///
+/// * `lambda` is negative, NaN or infinite (`InvalidParameter`)
+/// * `xys` is empty (`EmptyInput`)
+/// * `lambda` is zero and all x values are identical (`ConstantX`)
+/// * the slope or intercept is NaN or infinite (`NonFinite`)
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn ridge_lin_reg<I, F>(xys: I, x_mean: F, y_mean: F, lambda: F) -> Result<(F, F), LinRegError>
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    let lambda = check_lambda(lambda)?;
+
+    let mut xys = xys.peekable();
+    if xys.peek().is_none() {
+        return Err(LinRegError::EmptyInput);
+    }
+
+    let (sxx, _, sxy) = centered_sums(xys, x_mean, y_mean);
+    ridge_line(x_mean, y_mean, sxx, sxy, lambda)
+}
+
+/// Ridge regression from two slices
+///
+/// Convenience wrapper around [`ridge_lin_reg`](fn.ridge_lin_reg.html) that
+/// also fails if `xs` and `ys` differ in length (`LengthMismatch`).
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn ridge_linear_regression<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    lambda: F,
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    let xys = || {
+        xs.iter()
+            .map(|x| x.clone().into())
+            .zip(ys.iter().map(|y| y.clone().into()))
+    };
+    let (_, x_mean, y_mean) = means(xys())?;
+    ridge_lin_reg(xys(), x_mean, y_mean, lambda)
+}
+
+/// Ridge regression from tuples
+///
+/// The tuple counterpart of
+/// [`ridge_linear_regression`](fn.ridge_linear_regression.html).
+pub fn ridge_linear_regression_of<X, Y, F>(xys: &[(X, Y)], lambda: F) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    let xys = || {
+        xys.iter()
+            .map(|(x, y)| (x.clone().into(), y.clone().into()))
+    };
+    let (_, x_mean, y_mean) = means(xys())?;
+    ridge_lin_reg(xys(), x_mean, y_mean, lambda)
+}
+
+/// Fits the ridge line for every candidate penalty and keeps the one with
+/// the lowest GCV score
+fn select_line<I, F>(xys: I, lambdas: &[F]) -> Result<RidgeLine<F>, LinRegError>
+where
+    I: Iterator<Item = (F, F)> + Clone,
+    F: Float,
+{
+    if lambdas.is_empty() {
+        return Err(LinRegError::InvalidParameter);
+    }
+    for &lambda in lambdas {
+        check_lambda(lambda)?;
+    }
+
+    let (n, x_mean, y_mean) = means(xys.clone())?;
+    let (sxx, syy, sxy) = centered_sums(xys, x_mean, y_mean);
+
+    let mut best: Option<RidgeLine<F>> = None;
+    let mut error = LinRegError::TooFewPoints;
+
+    for &lambda in lambdas {
+        let (slope, intercept) = match ridge_line(x_mean, y_mean, sxx, sxy, lambda) {
+            Ok(line) => line,
+            Err(e) => {
+                error = e;
+                continue;
+            }
+        };
+
+        // rounding errors must not make the squared sum negative
+        let rss = (syy - (slope + slope) * sxy + slope * slope * sxx).max(F::zero());
+        let effective_df = F::one() + sxx / (sxx + lambda);
+        let gcv = gcv_score(n, rss, effective_df)?;
+
+        if gcv.is_finite() && best.map_or(true, |b| gcv < b.gcv) {
+            best = Some(RidgeLine {
+                slope,
+                intercept,
+                lambda,
+                effective_df,
+                gcv,
+            });
+        }
+    }
+
+    best.ok_or(error)
+}
+
+/// Ridge regression from two slices with the penalty chosen by generalized
+/// cross-validation
+///
+/// Fits the line for each of the candidate `lambdas` and returns the one
+/// with the lowest GCV score, an estimate of the prediction error that needs
+/// no refitting. A logarithmically spaced grid such as `0, 0.01, 0.1, 1, 10,
+/// ...` scaled to the spread of the x values usually works well.
+///
+/// ```rust
+/// use linreg::ridge_linear_regression_gcv;
+///
+/// let xs = [1.0, 2.0, 3.0, 4.0, 5.0];
+/// let ys = [2.0, 4.0, 5.0, 4.0, 5.0];
+///
+/// let line = ridge_linear_regression_gcv(&xs, &ys, &[0.0, 1.0, 10.0, 100.0]).unwrap();
+/// assert!(line.slope > 0.0 && line.slope < 0.6);
+/// ```
+///
+/// Returns an error if
+///
+/// * `lambdas` is empty or holds a negative, NaN or infinite value
+///   (`InvalidParameter`)
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * no candidate has a finite GCV score, e.g. with only two points and no
+///   penalty (`TooFewPoints`), or for the reasons listed under
+///   [`ridge_lin_reg`](fn.ridge_lin_reg.html)
+pub fn ridge_linear_regression_gcv<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    lambdas: &[F],
+) -> Result<RidgeLine<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    select_line(
+        xs.iter()
+            .map(|x| x.clone().into())
+            .zip(ys.iter().map(|y| y.clone().into())),
+        lambdas,
+    )
+}
+
+/// Ridge regression from tuples with the penalty chosen by generalized
+/// cross-validation
+///
+/// The tuple counterpart of
+/// [`ridge_linear_regression_gcv`](fn.ridge_linear_regression_gcv.html).
+pub fn ridge_linear_regression_gcv_of<X, Y, F>(
+    xys: &[(X, Y)],
+    lambdas: &[F],
+) -> Result<RidgeLine<F>, LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    select_line(
+        xys.iter()
+            .map(|(x, y)| (x.clone().into(), y.clone().into())),
+        lambdas,
+    )
+}
+
+/// Coefficients of a ridge regression with `P` coefficients
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RidgeRegressionResult<F, const P: usize> {
+    /// Fitted coefficients, in the order of the predictors
+    pub coefficients: [F; P],
+    /// Penalty the coefficients were fitted with
+    pub lambda: F,
+    /// Effective number of parameters, the trace of the hat matrix
+    pub effective_df: F,
+    /// Number of data points
+    pub n: usize,
+    /// Sum of squared residuals, without the penalty
+    pub rss: F,
+    /// Generalized cross-validation score, `NaN` unless `effective_df` is
+    /// smaller than `n`
+    pub gcv: F,
+}
+
+impl<F: Float, const P: usize> RidgeRegressionResult<F, P> {
+    /// Evaluates the fitted model for the predictors `row`
+    pub fn predict(&self, row: &[F; P]) -> F {
+        row.iter()
+            .zip(self.coefficients.iter())
+            .fold(F::zero(), |sum, (&x, &b)| sum + x * b)
+    }
+}
+
+impl<F: Float, const P: usize> MultiRegression<F, P> {
+    /// Solves for the coefficients with a ridge penalty
+    ///
+    /// Minimizes the sum of squared residuals plus `lambda` times the sum of
+    /// the squared coefficients for which `penalize` is `true`. Leave the
+    /// intercept column unpenalized so the fit does not depend on the origin
+    /// of y. The penalty is applied as `P` extra rows of the QR
+    /// decomposition, so the normal equations are never formed.
+    ///
+    /// Returns an error if `lambda` is negative, NaN or infinite
+    /// (`InvalidParameter`), or as described for
+    /// [`fit`](#method.fit); a positive penalty removes the
+    /// `RankDeficient` error for the penalized predictors.
+    pub fn fit_ridge(
+        &self,
+        lambda: F,
+        penalize: &[bool; P],
+    ) -> Result<RidgeRegressionResult<F, P>, LinRegError> {
+        let lambda = check_lambda(lambda)?;
+        if self.n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+
+        let mut r = self.r;
+        let mut qty = self.qty;
+        if lambda > F::zero() {
+            let root = lambda.sqrt();
+            for (k, &p) in penalize.iter().enumerate() {
+                if p {
+                    let mut row = [F::zero(); P];
+                    row[k] = root;
+                    add_row(&mut r, &mut qty, &mut row, F::zero());
+                }
+            }
+        }
+        check_rank(&r, self.n)?;
+
+        let mut coefficients = qty;
+        solve_upper(&r, &mut coefficients);
+
+        // |y - X beta|^2 = |Q^T y - R beta|^2 + the part outside the span of X
+        let mut rss = self.rss;
+        for k in 0..P {
+            let fitted = (k..P).fold(F::zero(), |sum, j| sum + self.r[k][j] * coefficients[j]);
+            let rest = self.qty[k] - fitted;
+            rss = rss + rest * rest;
+        }
+
+        // the trace of the hat matrix X (R_l^T R_l)^-1 X^T is the squared
+        // Frobenius norm of R R_l^-1
+        let mut effective_df = F::zero();
+        let mut column = [F::zero(); P];
+        for j in 0..P {
+            for (i, c) in column.iter_mut().enumerate() {
+                *c = if i == j { F::one() } else { F::zero() };
+            }
+            solve_upper(&r, &mut column);
+
+            for r_i in self.r.iter() {
+                let v = r_i
+                    .iter()
+                    .zip(column.iter())
+                    .fold(F::zero(), |sum, (&a, &b)| sum + a * b);
+                effective_df = effective_df + v * v;
+            }
+        }
+
+        Ok(RidgeRegressionResult {
+            coefficients,
+            lambda,
+            effective_df,
+            n: self.n,
+            rss,
+            gcv: gcv_score(self.n, rss, effective_df)?,
+        })
+    }
+
+    /// Solves for the coefficients with the ridge penalty chosen by
+    /// generalized cross-validation
+    ///
+    /// Calls [`fit_ridge`](#method.fit_ridge) for each of the candidate
+    /// `lambdas` and returns the fit with the lowest finite GCV score.
+    /// Returns `InvalidParameter` if `lambdas` is empty or holds an invalid
+    /// penalty, `TooFewPoints` if no candidate has a finite score, or the
+    /// error of the last failed fit.
+    pub fn fit_ridge_gcv(
+        &self,
+        lambdas: &[F],
+        penalize: &[bool; P],
+    ) -> Result<RidgeRegressionResult<F, P>, LinRegError> {
+        if lambdas.is_empty() {
+            return Err(LinRegError::InvalidParameter);
+        }
+        for &lambda in lambdas {
+            check_lambda(lambda)?;
+        }
+
+        let mut best: Option<RidgeRegressionResult<F, P>> = None;
+        let mut error = LinRegError::TooFewPoints;
+
+        for &lambda in lambdas {
+            match self.fit_ridge(lambda, penalize) {
+                Ok(fit) => {
+                    if fit.gcv.is_finite() && best.map_or(true, |b| fit.gcv < b.gcv) {
+                        best = Some(fit);
+                    }
+                }
+                Err(e) => error = e,
+            }
+        }
+
+        best.ok_or(error)
+    }
+}
+
+#[test]
+fn test_ridge_line() {
+    let xs: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    let ys: Vec<f64> = vec![2.0, 4.0, 5.0, 4.0, 5.0];
+
+    // Sxx = 10 and Sxy = 6
+    assert_eq!(Ok((0.6, 2.2)), ridge_linear_regression(&xs, &ys, 0.0));
+    let (slope, intercept) = ridge_linear_regression(&xs, &ys, 5.0).unwrap();
+    assert!((slope - 0.4).abs() < 1e-12);
+    assert!((intercept - 2.8).abs() < 1e-12);
+
+    let tuples: Vec<(f64, f64)> = xs.iter().cloned().zip(ys.iter().cloned()).collect();
+    assert_eq!(
+        Ok((slope, intercept)),
+        ridge_linear_regression_of(&tuples, 5.0)
+    );
+
+    // a penalty keeps constant x values solvable
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        ridge_linear_regression::<_, _, f64>(&[2.0, 2.0], &[1.0, 3.0], 0.0)
+    );
+    assert_eq!(
+        Ok((0.0, 2.0)),
+        ridge_linear_regression::<_, _, f64>(&[2.0, 2.0], &[1.0, 3.0], 1.0)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        ridge_linear_regression(&xs, &ys, -1.0)
+    );
+}
+
+#[test]
+fn test_ridge_multi_matches_line() {
+    let xs: Vec<f64> = vec![0.5, 1.5, 2.0, 3.25, 4.0, 6.5, 7.0];
+    let ys: Vec<f64> = vec![1.0, 2.5, 2.0, 4.0, 4.5, 7.0, 6.5];
+    let lambdas = [0.0, 0.5, 2.0, 8.0, 32.0];
+
+    let mut reg = MultiRegression::<f64, 2>::new();
+    for (&x, &y) in xs.iter().zip(ys.iter()) {
+        reg.push([1.0, x], y);
+    }
+
+    let ols = reg.fit().unwrap();
+    let unpenalized = reg.fit_ridge(0.0, &[false, true]).unwrap();
+    assert!((unpenalized.coefficients[1] - ols.coefficients[1]).abs() < 1e-12);
+    assert!((unpenalized.rss - ols.rss).abs() < 1e-12);
+    assert!((unpenalized.effective_df - 2.0).abs() < 1e-12);
+
+    for &lambda in &lambdas {
+        let fit = reg.fit_ridge(lambda, &[false, true]).unwrap();
+        let (slope, intercept) = ridge_linear_regression(&xs, &ys, lambda).unwrap();
+
+        assert!((fit.coefficients[0] - intercept).abs() < 1e-12);
+        assert!((fit.coefficients[1] - slope).abs() < 1e-12);
+        assert!((fit.predict(&[1.0, 3.0]) - (intercept + 3.0 * slope)).abs() < 1e-12);
+    }
+
+    let line = ridge_linear_regression_gcv(&xs, &ys, &lambdas).unwrap();
+    let fit = reg.fit_ridge_gcv(&lambdas, &[false, true]).unwrap();
+    assert_eq!(line.lambda, fit.lambda);
+    assert!((line.gcv - fit.gcv).abs() < 1e-12);
+    assert!((line.effective_df - fit.effective_df).abs() < 1e-12);
+    for &lambda in &lambdas {
+        assert!(fit.gcv <= reg.fit_ridge(lambda, &[false, true]).unwrap().gcv);
+    }
+}
+
+#[test]
+fn test_ridge_collinear() {
+    // the second predictor is twice the first, which a penalty resolves
+    let mut reg = MultiRegression::<f64, 2>::new();
+    reg.push([1.0, 2.0], 1.0);
+    reg.push([2.0, 4.0], 3.0);
+    reg.push([3.0, 6.0], 4.0);
+
+    assert_eq!(
+        Err(LinRegError::RankDeficient),
+        reg.fit_ridge(0.0, &[true, true])
+    );
+    let fit = reg.fit_ridge(1.0, &[true, true]).unwrap();
+    // the penalty splits the weight as 1:2 between the identical directions
+    assert!((fit.coefficients[1] - 2.0 * fit.coefficients[0]).abs() < 1e-12);
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        reg.fit_ridge_gcv(&[], &[true, true])
+    );
+    assert_eq!(
+        Err(LinRegError::TooFewPoints),
+        ridge_linear_regression_gcv(&[1.0, 2.0], &[3.0, 5.0], &[0.0])
+    );
+}
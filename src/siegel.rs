@@ -0,0 +1,210 @@
+use num_traits::Float;
+
+use error::LinRegError;
+use stats::median;
+
+/// Repeated median of `pair(i, j)` over all points `i` and the points `j`
+/// with a different x value
+///
+/// `medians` and `values` must hold `n` elements each.
+fn repeated_median<G, P, F>(point: &G, n: usize, medians: &mut [F], values: &mut [F], pair: P) -> F
+where
+    G: Fn(usize) -> (F, F),
+    P: Fn((F, F), (F, F)) -> F,
+    F: Float,
+{
+    let mut m = 0;
+    for i in 0..n {
+        let p_i = point(i);
+
+        let mut k = 0;
+        for j in 0..n {
+            let p_j = point(j);
+            if p_j.0 != p_i.0 {
+                values[k] = pair(p_i, p_j);
+                k += 1;
+            }
+        }
+
+        if k > 0 {
+            medians[m] = median(&mut values[..k]);
+            m += 1;
+        }
+    }
+
+    median(&mut medians[..m])
+}
+
+/// Fits the repeated medians line to the `n` points returned by `point`
+fn fit_siegel<G, F>(point: G, n: usize, scratch: &mut [F]) -> Result<(F, F), LinRegError>
+where
+    G: Fn(usize) -> (F, F),
+    F: Float,
+{
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+    if scratch.len() < 2 * n {
+        return Err(LinRegError::InvalidParameter);
+    }
+
+    let x0 = point(0).0;
+    let mut x_varies = false;
+    for i in 0..n {
+        let (x, y) = point(i);
+        if !x.is_finite() || !y.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+        x_varies = x_varies || x != x0;
+    }
+    if !x_varies {
+        return Err(LinRegError::ConstantX);
+    }
+
+    let (medians, values) = scratch.split_at_mut(n);
+    let values = &mut values[..n];
+
+    let slope = repeated_median(&point, n, medians, values, |(xi, yi), (xj, yj)| {
+        (yj - yi) / (xj - xi)
+    });
+    // the intercept of the line through both points
+    let intercept = repeated_median(&point, n, medians, values, |(xi, yi), (xj, yj)| {
+        (xj * yi - xi * yj) / (xj - xi)
+    });
+
+    if !slope.is_finite() || !intercept.is_finite() {
+        return Err(LinRegError::NonFinite);
+    }
+
+    Ok((slope, intercept))
+}
+
+/// Siegel's repeated medians regression from two slices
+///
+/// For every point, takes the median of the slopes to all points with a
+/// different x value; the slope of the line is the median of these
+/// medians. The intercept is found the same way from the intercepts of the
+/// lines through each pair. Nearly half of the points can be arbitrarily
+/// wrong without moving the line far, compared to 29% for Theil–Sen.
+///
+/// Takes O(n²) time. `scratch` must hold at least `2 * xs.len()` values; its
+/// contents are overwritten, and nothing is allocated.
+///
+/// ```rust
+/// use linreg::siegel_regression;
+///
+/// // four of eleven points are corrupted
+/// let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+/// let ys = [1.0, 3.0, 50.0, 7.0, 60.0, 11.0, 70.0, 15.0, 80.0, 19.0, 21.0];
+///
+/// let mut scratch = [0.0; 22];
+/// let (slope, intercept): (f64, f64) = siegel_regression(&xs, &ys, &mut scratch).unwrap();
+/// assert!((slope - 2.0).abs() < 1e-12);
+/// assert!((intercept - 1.0).abs() < 1e-12);
+/// ```
+///
+/// Returns an error if
+///
+/// * `xs` and `ys` differ in length (`LengthMismatch`)
+/// * `xs` and `ys` are empty (`EmptyInput`)
+/// * `scratch` is too short (`InvalidParameter`)
+/// * all x values are identical (`ConstantX`)
+/// * a value, the slope or the intercept is NaN or infinite (`NonFinite`)
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn siegel_regression<X, Y, F>(
+    xs: &[X],
+    ys: &[Y],
+    scratch: &mut [F],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    fit_siegel(
+        |i| (xs[i].clone().into(), ys[i].clone().into()),
+        xs.len(),
+        scratch,
+    )
+}
+
+/// Siegel's repeated medians regression from tuples
+///
+/// The tuple counterpart of
+/// [`siegel_regression`](fn.siegel_regression.html).
+pub fn siegel_regression_of<X, Y, F>(
+    xys: &[(X, Y)],
+    scratch: &mut [F],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    fit_siegel(
+        |i| (xys[i].0.clone().into(), xys[i].1.clone().into()),
+        xys.len(),
+        scratch,
+    )
+}
+
+#[test]
+fn test_siegel_example() {
+    let xys: [(u8, u8); 5] = [(1, 2), (2, 4), (3, 5), (4, 4), (5, 5)];
+
+    // median slopes per point: 9/8, 2/3, 1/2, 1/3, 13/24
+    let mut scratch = [0.0; 10];
+    let (slope, intercept): (f64, f64) = siegel_regression_of(&xys, &mut scratch).unwrap();
+    assert!((slope - 13.0 / 24.0).abs() < 1e-12);
+    assert!((intercept - 8.0 / 3.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_siegel_breakdown() {
+    // 5 of 12 points are corrupted, more than Theil-Sen can handle
+    let xs: [f64; 12] = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0, 11.0];
+    let mut ys = [0.0; 12];
+    for (i, (y, &x)) in ys.iter_mut().zip(xs.iter()).enumerate() {
+        *y = if (i % 5 < 2 && i < 10) || i == 11 {
+            3.0 * x - 50.0
+        } else {
+            2.0 * x + 1.0
+        };
+    }
+
+    let mut scratch = [0.0; 24];
+    let (slope, intercept): (f64, f64) = siegel_regression(&xs, &ys, &mut scratch).unwrap();
+    assert!((slope - 2.0).abs() < 1e-12);
+    assert!((intercept - 1.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_siegel_errors() {
+    let mut scratch = [0.0; 4];
+
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        siegel_regression(&[1.0, 2.0], &[1.0], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        siegel_regression_of::<f64, f64, f64>(&[], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        siegel_regression(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        siegel_regression(&[2.0, 2.0], &[1.0, 3.0], &mut scratch)
+    );
+    assert_eq!(
+        Err(LinRegError::NonFinite),
+        siegel_regression(&[1.0, 2.0], &[1.0, f64::INFINITY], &mut scratch)
+    );
+}
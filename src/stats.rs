@@ -0,0 +1,144 @@
+//! Summary statistics shared by the estimators
+
+use num_traits::Float;
+
+use core::cmp::Ordering;
+
+use dist::constant;
+use error::LinRegError;
+
+/// Number of values and their mean
+///
+/// Returns an error if there are no values (`EmptyInput`) or their number
+/// cannot be represented by `F` (`CountNotRepresentable`).
+#[cfg_attr(not(feature = "alloc"), allow(dead_code))]
+pub(crate) fn mean<I, F>(values: I) -> Result<(usize, F), LinRegError>
+where
+    I: Iterator<Item = F>,
+    F: Float,
+{
+    let (n, sum) = values.fold((0, F::zero()), |(n, s), v| (n + 1, s + v));
+
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+
+    let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+    Ok((n, sum / n_f))
+}
+
+/// Number of points and the means of x and y
+///
+/// Fails for the same reasons as `mean`.
+pub(crate) fn means<I, F>(xys: I) -> Result<(usize, F, F), LinRegError>
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    let (n, x_sum, y_sum) = xys.fold((0, F::zero(), F::zero()), |(n, sx, sy), (x, y)| {
+        (n + 1, sx + x, sy + y)
+    });
+
+    if n == 0 {
+        return Err(LinRegError::EmptyInput);
+    }
+
+    let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+    Ok((n, x_sum / n_f, y_sum / n_f))
+}
+
+/// Sums of the squared and multiplied deviations from the means
+///
+/// Returns `SUM (x-mean(x))^2`, `SUM (y-mean(y))^2` and
+/// `SUM (x-mean(x)) (y-mean(y))`.
+pub(crate) fn centered_sums<I, F>(xys: I, x_mean: F, y_mean: F) -> (F, F, F)
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    xys.fold((F::zero(), F::zero(), F::zero()), |(xx, yy, xy), (x, y)| {
+        (
+            xx + (x - x_mean) * (x - x_mean),
+            yy + (y - y_mean) * (y - y_mean),
+            xy + (x - x_mean) * (y - y_mean),
+        )
+    })
+}
+
+/// The x value shared by all points, `None` if the x values vary or there
+/// are no points
+///
+/// Compares the values themselves, as the mean of identical values can be
+/// off by a rounding error.
+pub(crate) fn common_x<I, F>(mut xys: I) -> Option<F>
+where
+    I: Iterator<Item = (F, F)>,
+    F: Float,
+{
+    let (x0, _) = xys.next()?;
+    if xys.all(|(x, _)| x == x0) {
+        Some(x0)
+    } else {
+        None
+    }
+}
+
+/// Median of `key` over `values`, reordering them
+///
+/// For an even number of values, the mean of the two middle ones.
+pub(crate) fn median_by<T, K, F>(values: &mut [T], key: K) -> F
+where
+    K: Fn(&T) -> F,
+    F: Float,
+{
+    let compare = |a: &T, b: &T| key(a).partial_cmp(&key(b)).unwrap_or(Ordering::Equal);
+
+    let n = values.len();
+    let (lower, upper, _) = values.select_nth_unstable_by(n / 2, compare);
+    let upper = key(upper);
+    if n % 2 == 1 {
+        return upper;
+    }
+
+    let lower = lower.iter().fold(F::neg_infinity(), |m, v| m.max(key(v)));
+    (lower + upper) * constant(0.5)
+}
+
+/// Median of `values`, reordering them
+pub(crate) fn median<F: Float>(values: &mut [F]) -> F {
+    median_by(values, |&v| v)
+}
+
+#[test]
+fn test_means() {
+    let xys = [(1.0, 2.0), (2.0, 4.0), (3.0, 9.0)];
+
+    assert_eq!(Ok((3, 2.0, 5.0)), means(xys.iter().cloned()));
+    assert_eq!(Ok((3, 5.0)), mean(xys.iter().map(|p| p.1)));
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        means(xys[..0].iter().cloned())
+    );
+    assert_eq!(
+        (2.0, 26.0, 7.0),
+        centered_sums(xys.iter().cloned(), 2.0, 5.0)
+    );
+
+    assert_eq!(None, common_x(xys.iter().cloned()));
+    assert_eq!(
+        Some(0.1),
+        common_x([(0.1, 1.0), (0.1, 2.0)].iter().cloned())
+    );
+    assert_eq!(None, common_x(xys[..0].iter().cloned()));
+}
+
+#[test]
+fn test_median() {
+    assert_eq!(3.0, median(&mut [5.0, 1.0, 3.0, 4.0, 2.0]));
+    assert_eq!(2.5, median(&mut [4.0, 1.0, 3.0, 2.0]));
+    assert_eq!(7.0, median(&mut [7.0]));
+    assert_eq!(
+        2.0,
+        median_by(&mut [(3.0, 0.0), (1.0, 9.0), (2.0, 5.0)], |p| p.0)
+    );
+}
@@ -0,0 +1,562 @@
+use num_traits::Float;
+
+use core::cmp::Ordering;
+#[cfg(test)]
+use std::vec::Vec;
+
+use dist::{constant, normal_quantile};
+use error::LinRegError;
+use stats::median_by;
+
+/// Theil–Sen estimator, a regression line that tolerates outliers
+///
+/// The slope is the median of the slopes between all pairs of points with
+/// different x values, and the intercept is the median of `y - slope * x`.
+/// Up to 29% of the points can be arbitrarily wrong without moving the
+/// line far.
+///
+/// The pairwise slopes are never materialized: the median is found by
+/// bisection on the slope, counting the pairs below each candidate as
+/// inversions with a merge sort. Every step takes O(n log n) time, and a
+/// slope takes up to about 64 steps, as the bisection halves the bit
+/// patterns of the floats. The two middle slopes of an even number of pairs
+/// share their steps until the bisection separates them, while the
+/// confidence interval needs two more slopes of its own. All memory is
+/// borrowed from a scratch buffer of `2 * n` pairs, so nothing is allocated.
+///
+/// ```rust
+/// use linreg::TheilSen;
+///
+/// let xs = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+/// let ys = [3.0, 5.0, 7.0, 90.0, 11.0, 13.0];
+///
+/// let mut scratch = [(0.0f64, 0.0); 12];
+/// let mut theil_sen = TheilSen::new(&xs, &ys, &mut scratch).unwrap();
+/// let (slope, intercept) = theil_sen.line();
+///
+/// assert!((slope - 2.0).abs() < 1e-12);
+/// assert!((intercept - 1.0).abs() < 1e-12);
+/// ```
+#[derive(Debug)]
+pub struct TheilSen<'a, F> {
+    // the data points, sorted by x and then by y
+    points: &'a mut [(F, F)],
+    // scratch space for the merge sort
+    buffer: &'a mut [(F, F)],
+    // number of pairs with different x values
+    pairs: u64,
+    // 18 times the variance of Kendall's S, corrected for tied x values
+    variance: F,
+    min_slope: F,
+    max_slope: F,
+}
+
+/// Counts the pairs `i < j` with `values[j].0 < values[i].0`
+///
+/// Sorts the first elements with a merge sort that uses the second elements
+/// as temporary storage.
+fn count_inversions<F: Float>(values: &mut [(F, F)]) -> u64 {
+    let n = values.len();
+    if n < 2 {
+        return 0;
+    }
+
+    let mid = n / 2;
+    let mut count = count_inversions(&mut values[..mid]) + count_inversions(&mut values[mid..]);
+
+    let (mut i, mut j) = (0, mid);
+    for k in 0..n {
+        if j < n && (i == mid || values[j].0 < values[i].0) {
+            // every remaining element of the left half is larger
+            count += (mid - i) as u64;
+            values[k].1 = values[j].0;
+            j += 1;
+        } else {
+            values[k].1 = values[i].0;
+            i += 1;
+        }
+    }
+
+    for v in values.iter_mut() {
+        v.0 = v.1;
+    }
+
+    count
+}
+
+/// A value between `lo` and `hi` that splits the floating-point numbers in
+/// between in half
+///
+/// Halving the bit patterns rather than the distance takes at most about 64
+/// steps to narrow any range down to neighbouring floats, however far the
+/// outliers put the extreme slopes.
+fn midpoint<F: Float>(lo: F, hi: F) -> F {
+    const SIGN: u64 = 1 << 63;
+    // maps the floats to integers in the same order
+    let key = |v: F| {
+        let bits = v.to_f64().unwrap_or(0.0).to_bits();
+        if bits & SIGN != 0 {
+            !bits
+        } else {
+            bits | SIGN
+        }
+    };
+    let value = |k: u64| f64::from_bits(if k & SIGN != 0 { k & !SIGN } else { !k });
+
+    let (a, b) = (key(lo), key(hi));
+    match F::from(value(a + (b - a) / 2)) {
+        Some(mid) if mid > lo && mid < hi => mid,
+        // the f64 midpoint may round to an end for narrower types
+        _ => lo + (hi - lo) * constant(0.5),
+    }
+}
+
+impl<'a, F: Float> TheilSen<'a, F> {
+    /// Prepares the estimator for the points in two slices
+    ///
+    /// `scratch` must hold at least `2 * xs.len()` pairs; its contents are
+    /// overwritten. Returns an error if
+    ///
+    /// * `xs` and `ys` differ in length (`LengthMismatch`)
+    /// * `xs` and `ys` are empty (`EmptyInput`)
+    /// * `scratch` is too short (`InvalidParameter`)
+    /// * a value is NaN or infinite (`NonFinite`)
+    /// * all x values are identical (`ConstantX`)
+    pub fn new<X, Y>(xs: &[X], ys: &[Y], scratch: &'a mut [(F, F)]) -> Result<Self, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        if xs.len() != ys.len() {
+            return Err(LinRegError::LengthMismatch);
+        }
+
+        TheilSen::from_points(
+            xs.iter()
+                .map(|x| x.clone().into())
+                .zip(ys.iter().map(|y| y.clone().into())),
+            xs.len(),
+            scratch,
+        )
+    }
+
+    /// Prepares the estimator for a slice of tuples
+    ///
+    /// The tuple counterpart of [`new`](#method.new).
+    pub fn of<X, Y>(xys: &[(X, Y)], scratch: &'a mut [(F, F)]) -> Result<Self, LinRegError>
+    where
+        X: Clone + Into<F>,
+        Y: Clone + Into<F>,
+    {
+        TheilSen::from_points(
+            xys.iter()
+                .map(|(x, y)| (x.clone().into(), y.clone().into())),
+            xys.len(),
+            scratch,
+        )
+    }
+
+    fn from_points<I>(xys: I, n: usize, scratch: &'a mut [(F, F)]) -> Result<Self, LinRegError>
+    where
+        I: Iterator<Item = (F, F)>,
+    {
+        if n == 0 {
+            return Err(LinRegError::EmptyInput);
+        }
+        if scratch.len() < 2 * n {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        let (points, rest) = scratch.split_at_mut(n);
+        let buffer = &mut rest[..n];
+
+        for (p, (x, y)) in points.iter_mut().zip(xys) {
+            if !x.is_finite() || !y.is_finite() {
+                return Err(LinRegError::NonFinite);
+            }
+            *p = (x, y);
+        }
+        points.sort_unstable_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or(Ordering::Equal)
+                .then(a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal))
+        });
+
+        let n_f = F::from(n).ok_or(LinRegError::CountNotRepresentable)?;
+        let mut pairs = (n as u64) * (n as u64 - 1) / 2;
+        // SUM t(t-1)(2t+5) over the groups of t identical x values
+        let mut ties = F::zero();
+
+        // the extreme slopes are always between neighbouring x values, so it
+        // is enough to track the y range of the current and previous group
+        let mut min_slope = F::infinity();
+        let mut max_slope = F::neg_infinity();
+        let mut previous: Option<(F, F, F)> = None;
+
+        let mut start = 0;
+        while start < n {
+            let x = points[start].0;
+            let mut end = start + 1;
+            while end < n && points[end].0 == x {
+                end += 1;
+            }
+
+            let t = (end - start) as u64;
+            pairs -= t * (t - 1) / 2;
+            let t_f = F::from(t).ok_or(LinRegError::CountNotRepresentable)?;
+            ties = ties + t_f * (t_f - F::one()) * (t_f + t_f + constant(5.0));
+
+            // y is sorted within the group
+            let (y_min, y_max) = (points[start].1, points[end - 1].1);
+            if let Some((prev_x, prev_min, prev_max)) = previous {
+                let dx = x - prev_x;
+                min_slope = min_slope.min((y_min - prev_max) / dx);
+                max_slope = max_slope.max((y_max - prev_min) / dx);
+            }
+            previous = Some((x, y_min, y_max));
+            start = end;
+        }
+
+        if pairs == 0 {
+            return Err(LinRegError::ConstantX);
+        }
+        if !min_slope.is_finite() || !max_slope.is_finite() {
+            return Err(LinRegError::NonFinite);
+        }
+
+        let variance = n_f * (n_f - F::one()) * (n_f + n_f + constant(5.0)) - ties;
+
+        Ok(TheilSen {
+            points,
+            buffer,
+            pairs,
+            variance,
+            min_slope,
+            max_slope,
+        })
+    }
+
+    /// Number of pairs of points with different x values, i.e. the number of
+    /// pairwise slopes
+    pub fn pairs(&self) -> u64 {
+        self.pairs
+    }
+
+    /// Number of pairwise slopes smaller than `slope`
+    fn count_below(&mut self, slope: F) -> u64 {
+        for (b, &(x, y)) in self.buffer.iter_mut().zip(self.points.iter()) {
+            b.0 = y - slope * x;
+        }
+
+        // for x_i < x_j, the pair's slope is below `slope` exactly when the
+        // residual of j is smaller; tied x values are sorted by y and never
+        // count
+        count_inversions(self.buffer)
+    }
+
+    /// The `k`-th smallest pairwise slope, given `lo` with fewer than `k`
+    /// slopes below it and `hi` with at least `k`
+    fn bisect(&mut self, k: u64, mut lo: F, mut hi: F) -> F {
+        // once they are neighbouring floats, the k-th slope is `lo`
+        loop {
+            let mid = midpoint(lo, hi);
+            if mid <= lo || mid >= hi {
+                return lo;
+            }
+
+            if self.count_below(mid) < k {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+    }
+
+    /// The `k`-th smallest pairwise slope, counting from one
+    fn slope_of_rank(&mut self, k: u64) -> F {
+        if k <= 1 {
+            return self.min_slope;
+        }
+        if self.count_below(self.max_slope) < k {
+            return self.max_slope;
+        }
+
+        self.bisect(k, self.min_slope, self.max_slope)
+    }
+
+    /// The `k`-th and `k + 1`-th smallest pairwise slopes
+    ///
+    /// Both are bracketed by the same bisection until a candidate has
+    /// exactly `k` slopes below it and separates them.
+    fn slopes_of_ranks(&mut self, k: u64) -> (F, F) {
+        let below_max = self.count_below(self.max_slope);
+        if below_max <= k {
+            return (self.slope_of_rank(k), self.max_slope);
+        }
+
+        // fewer than k slopes lie below `lo`, more than k below `hi`
+        let (mut lo, mut hi) = (self.min_slope, self.max_slope);
+        loop {
+            let mid = midpoint(lo, hi);
+            if mid <= lo || mid >= hi {
+                return (lo, lo);
+            }
+
+            let below = self.count_below(mid);
+            if below < k {
+                lo = mid;
+            } else if below > k {
+                hi = mid;
+            } else {
+                return (self.bisect(k, lo, mid), self.bisect(k + 1, mid, hi));
+            }
+        }
+    }
+
+    /// Median of the pairwise slopes
+    pub fn slope(&mut self) -> F {
+        let k = self.pairs / 2 + 1;
+        if self.pairs % 2 == 1 {
+            return self.slope_of_rank(k);
+        }
+
+        let (lower, upper) = self.slopes_of_ranks(k - 1);
+        (lower + upper) * constant(0.5)
+    }
+
+    /// Slope and intercept of the Theil–Sen line
+    ///
+    /// The intercept is the median of `y - slope * x` over all points.
+    pub fn line(&mut self) -> (F, F) {
+        let slope = self.slope();
+
+        for (b, &(x, y)) in self.buffer.iter_mut().zip(self.points.iter()) {
+            b.0 = y - slope * x;
+        }
+
+        (slope, median_by(self.buffer, |b| b.0))
+    }
+
+    /// Confidence interval of the slope
+    ///
+    /// Uses Sen's distribution-free interval, which picks the pairwise
+    /// slopes at the ranks a normal approximation of Kendall's tau allows
+    /// around the median. `level` is the confidence level, e.g. `0.95` for a
+    /// 95% interval.
+    ///
+    /// Returns `[lower, upper]` or an error if
+    ///
+    /// * `level` is not strictly between zero and one (`InvalidParameter`)
+    /// * there are too few points for the requested level (`TooFewPoints`)
+    pub fn slope_confidence_interval(&mut self, level: F) -> Result<[F; 2], LinRegError> {
+        if !(level > F::zero() && level < F::one()) {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        let z = normal_quantile((F::one() + level) * constant(0.5));
+        let c = z * (self.variance / constant(18.0)).sqrt();
+        let pairs = F::from(self.pairs).ok_or(LinRegError::CountNotRepresentable)?;
+
+        // slopes of rank m and pairs + 1 - m bound the interval
+        let m = ((pairs - c) * constant(0.5)).floor();
+        if m.is_nan() || m < F::one() {
+            return Err(LinRegError::TooFewPoints);
+        }
+        let m = m.to_u64().ok_or(LinRegError::CountNotRepresentable)?;
+
+        let lower = self.slope_of_rank(m);
+        let upper = self.slope_of_rank(self.pairs + 1 - m);
+        Ok([lower, upper])
+    }
+}
+
+/// Theil–Sen regression from two slices
+///
+/// Allocates the scratch buffer of a [`TheilSen`](struct.TheilSen.html)
+/// estimator, so it requires the `alloc` feature. Fails for the same reasons
+/// as [`TheilSen::new`](struct.TheilSen.html#method.new).
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+#[cfg(feature = "alloc")]
+pub fn theil_sen_regression<X, Y, F>(xs: &[X], ys: &[Y]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    let mut scratch = vec![(F::zero(), F::zero()); 2 * xs.len()];
+    TheilSen::new(xs, ys, &mut scratch).map(|mut t| t.line())
+}
+
+/// Theil–Sen regression from tuples
+///
+/// The tuple counterpart of
+/// [`theil_sen_regression`](fn.theil_sen_regression.html).
+#[cfg(feature = "alloc")]
+pub fn theil_sen_regression_of<X, Y, F>(xys: &[(X, Y)]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    F: Float,
+{
+    let mut scratch = vec![(F::zero(), F::zero()); 2 * xys.len()];
+    TheilSen::of(xys, &mut scratch).map(|mut t| t.line())
+}
+
+#[cfg(test)]
+fn pairwise_slopes(xys: &[(f64, f64)]) -> Vec<f64> {
+    let mut slopes = Vec::new();
+    for (i, &(x1, y1)) in xys.iter().enumerate() {
+        for &(x2, y2) in &xys[i + 1..] {
+            if x1 != x2 {
+                slopes.push((y2 - y1) / (x2 - x1));
+            }
+        }
+    }
+    slopes.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    slopes
+}
+
+#[test]
+fn test_theil_sen_matches_naive() {
+    for &n in &[2usize, 3, 8, 9, 40, 41] {
+        // repeated x values and a few wild outliers
+        let xys: Vec<(f64, f64)> = (0..n)
+            .map(|i| {
+                let x = f64::from((i * 7 % 13) as u32) * 0.5;
+                let y = 1.5 * x - 2.0 + f64::from((i * 5 % 11) as u32) * 0.1;
+                (x, if i % 9 == 4 { y + 100.0 } else { y })
+            })
+            .collect();
+
+        let slopes = pairwise_slopes(&xys);
+        let m = slopes.len();
+        let expected = if m % 2 == 1 {
+            slopes[m / 2]
+        } else {
+            (slopes[m / 2 - 1] + slopes[m / 2]) / 2.0
+        };
+
+        let mut scratch = vec![(0.0, 0.0); 2 * n];
+        let mut theil_sen = TheilSen::of(&xys, &mut scratch).unwrap();
+        assert_eq!(m as u64, theil_sen.pairs());
+
+        let (slope, intercept) = theil_sen.line();
+        assert!((slope - expected).abs() < 1e-12);
+
+        let mut residuals: Vec<f64> = xys.iter().map(|&(x, y)| y - slope * x).collect();
+        residuals.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let expected = if n % 2 == 1 {
+            residuals[n / 2]
+        } else {
+            (residuals[n / 2 - 1] + residuals[n / 2]) / 2.0
+        };
+        assert!((intercept - expected).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn test_theil_sen_near_tied_outlier() {
+    // a glitch next to x = 10 makes the largest slope about 1e15
+    let mut xys: Vec<(f64, f64)> = (0..50)
+        .map(|i| {
+            let x = f64::from(i);
+            (x, 0.1235 * x + 1.0 + f64::from(i * 13 % 7) * 1e-3)
+        })
+        .collect();
+    xys.push((10.0 + 1e-9, 1e6));
+
+    let slopes = pairwise_slopes(&xys);
+    let m = slopes.len();
+    let expected = if m % 2 == 1 {
+        slopes[m / 2]
+    } else {
+        (slopes[m / 2 - 1] + slopes[m / 2]) / 2.0
+    };
+
+    let mut scratch = vec![(0.0, 0.0); 2 * xys.len()];
+    let slope = TheilSen::of(&xys, &mut scratch).unwrap().slope();
+    assert!((slope - expected).abs() < 1e-12);
+    assert!((slope - 0.1235).abs() < 1e-3);
+
+    // the bisection also works in single precision
+    let xys32: Vec<(f32, f32)> = xys.iter().map(|&(x, y)| (x as f32, y as f32)).collect();
+    let mut scratch = vec![(0.0f32, 0.0f32); 2 * xys32.len()];
+    let slope32 = TheilSen::of(&xys32, &mut scratch).unwrap().slope();
+    assert!((slope32 - 0.1235).abs() < 1e-3);
+}
+
+#[test]
+fn test_theil_sen_confidence_interval() {
+    let xys: Vec<(f64, f64)> = (0..30)
+        .map(|i| {
+            let x = f64::from(i);
+            (x, 0.8 * x + f64::from(i * 17 % 10) - 4.5)
+        })
+        .collect();
+    let slopes = pairwise_slopes(&xys);
+
+    let mut scratch = vec![(0.0, 0.0); 60];
+    let mut theil_sen = TheilSen::of(&xys, &mut scratch).unwrap();
+
+    // C = 1.96 * sqrt(30 * 29 * 65 / 18) = 109.9, so m = floor((435 - C) / 2)
+    let [lower, upper] = theil_sen.slope_confidence_interval(0.95).unwrap();
+    assert!((lower - slopes[161]).abs() < 1e-12);
+    assert!((upper - slopes[435 - 162]).abs() < 1e-12);
+
+    let slope = theil_sen.slope();
+    assert!(lower < slope && slope < upper);
+
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        theil_sen.slope_confidence_interval(1.0)
+    );
+
+    let mut scratch = [(0.0, 0.0); 6];
+    let mut theil_sen = TheilSen::new(&[1.0, 2.0, 3.0], &[1.0, 3.0, 2.0], &mut scratch).unwrap();
+    assert_eq!(
+        Err(LinRegError::TooFewPoints),
+        theil_sen.slope_confidence_interval(0.95)
+    );
+}
+
+#[test]
+fn test_theil_sen_errors() {
+    let mut scratch = [(0.0, 0.0); 4];
+
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        TheilSen::new(&[1.0, 2.0], &[1.0], &mut scratch).map(|_| ())
+    );
+    assert_eq!(
+        Err(LinRegError::EmptyInput),
+        TheilSen::<f64>::of::<f64, f64>(&[], &mut scratch).map(|_| ())
+    );
+    assert_eq!(
+        Err(LinRegError::InvalidParameter),
+        TheilSen::new(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0], &mut scratch).map(|_| ())
+    );
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        TheilSen::new(&[2.0, 2.0], &[1.0, 3.0], &mut scratch).map(|_| ())
+    );
+    assert_eq!(
+        Err(LinRegError::NonFinite),
+        TheilSen::new(&[1.0, f64::NAN], &[1.0, 3.0], &mut scratch).map(|_| ())
+    );
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_theil_sen_regression() {
+    let xs: Vec<u8> = vec![1, 2, 3, 4, 5];
+    let ys: Vec<u8> = vec![2, 4, 5, 4, 5];
+
+    // slopes: 2, 1.5, 2/3, 3/4, 1, 0, 1/3, -1, 0, 1
+    let (slope, intercept): (f64, f64) = theil_sen_regression(&xs, &ys).unwrap();
+    assert!((slope - 0.708_333_333_333_333).abs() < 1e-12);
+    assert!((intercept - 1.458_333_333_333_333).abs() < 1e-12);
+
+    let tuples: Vec<(u8, u8)> = xs.iter().cloned().zip(ys.iter().cloned()).collect();
+    assert_eq!(Ok((slope, intercept)), theil_sen_regression_of(&tuples));
+}
@@ -0,0 +1,244 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+
+/// Checks a single weight
+fn check_weight<F: Float>(w: F) -> Result<F, LinRegError> {
+    if !w.is_finite() {
+        Err(LinRegError::NonFinite)
+    } else if w < F::zero() {
+        Err(LinRegError::NegativeWeight)
+    } else {
+        Ok(w)
+    }
+}
+
+/// Calculates weighted means of x and y values
+///
+/// Returns `(x_mean, y_mean)`.
+pub(crate) fn weighted_means<I, F>(xyws: I) -> Result<(F, F), LinRegError>
+where
+    I: Iterator<Item = (F, F, F)>,
+    F: Float,
+{
+    let mut w_sum = F::zero();
+    let mut wx_sum = F::zero();
+    let mut wy_sum = F::zero();
+    let mut empty = true;
+
+    for (x, y, w) in xyws {
+        let w = check_weight(w)?;
+        empty = false;
+        if w == F::zero() {
+            continue;
+        }
+
+        w_sum = w_sum + w;
+        wx_sum = wx_sum + w * x;
+        wy_sum = wy_sum + w * y;
+    }
+
+    if empty {
+        return Err(LinRegError::EmptyInput);
+    }
+    if w_sum == F::zero() {
+        return Err(LinRegError::ZeroWeights);
+    }
+
+    Ok((wx_sum / w_sum, wy_sum / w_sum))
+}
+
+/// Calculates a weighted linear regression
+///
+/// Lower-level weighted linear regression function, the weighted counterpart
+/// of [`lin_reg`](fn.lin_reg.html). Assumes that `x_mean` and `y_mean` are the
+/// weighted means of the x and y values. Each item of `xyws` is an
+/// `(x, y, weight)` triple. Returns an error if
+///
+/// * `xyws` is empty (`EmptyInput`)
+/// * a weight is negative (`NegativeWeight`)
+/// * all weights are zero (`ZeroWeights`)
+/// * all x values with a nonzero weight are identical (`ConstantX`)
+/// * the slope or intercept is NaN or infinite (`NonFinite`)
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn weighted_lin_reg<I, F>(xyws: I, x_mean: F, y_mean: F) -> Result<(F, F), LinRegError>
+where
+    I: Iterator<Item = (F, F, F)>,
+    F: Float,
+{
+    // SUM w (x-mean(x))^2
+    let mut xxm2 = F::zero();
+
+    // SUM w (x-mean(x)) (y-mean(y))
+    let mut xmym2 = F::zero();
+
+    let mut empty = true;
+    let mut w_sum = F::zero();
+
+    // the first x value with a nonzero weight, used to detect a vertical line
+    let mut first_x = None;
+    let mut x_varies = false;
+
+    for (x, y, w) in xyws {
+        let w = check_weight(w)?;
+        empty = false;
+        // skipped rather than multiplied by zero, which is NaN for infinite
+        // values
+        if w == F::zero() {
+            continue;
+        }
+        w_sum = w_sum + w;
+
+        match first_x {
+            None => first_x = Some(x),
+            Some(x0) => x_varies = x_varies || x != x0,
+        }
+
+        xxm2 = xxm2 + w * (x - x_mean) * (x - x_mean);
+        xmym2 = xmym2 + w * (x - x_mean) * (y - y_mean);
+    }
+
+    if empty {
+        return Err(LinRegError::EmptyInput);
+    }
+    if w_sum == F::zero() {
+        return Err(LinRegError::ZeroWeights);
+    }
+    if !x_varies || xxm2 == F::zero() {
+        return Err(LinRegError::ConstantX);
+    }
+
+    let slope = xmym2 / xxm2;
+    let intercept = y_mean - slope * x_mean;
+
+    if !slope.is_finite() || !intercept.is_finite() {
+        return Err(LinRegError::NonFinite);
+    }
+
+    Ok((slope, intercept))
+}
+
+/// Weighted linear regression from three slices
+///
+/// Calculates the weighted least squares regression from slices of x-values,
+/// y-values and non-negative weights. Points with a weight of zero are
+/// ignored. Fails for the reasons listed for
+/// [`weighted_lin_reg`](fn.weighted_lin_reg.html), or if the slices differ in
+/// length (`LengthMismatch`).
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn weighted_linear_regression<X, Y, W, F>(
+    xs: &[X],
+    ys: &[Y],
+    ws: &[W],
+) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    W: Clone + Into<F>,
+    F: Float,
+{
+    if xs.len() != ys.len() || xs.len() != ws.len() {
+        return Err(LinRegError::LengthMismatch);
+    }
+
+    let xyws = || {
+        xs.iter()
+            .zip(ys.iter())
+            .zip(ws.iter())
+            .map(|((x, y), w)| (x.clone().into(), y.clone().into(), w.clone().into()))
+    };
+
+    let (x_mean, y_mean) = weighted_means(xyws())?;
+    weighted_lin_reg(xyws(), x_mean, y_mean)
+}
+
+/// Weighted linear regression from tuples
+///
+/// Calculates the weighted least squares regression from a slice of
+/// `(x, y, weight)` tuples. Fails for the reasons listed for
+/// [`weighted_lin_reg`](fn.weighted_lin_reg.html).
+///
+/// Returns `Ok(slope, intercept)` of the regression line.
+pub fn weighted_linear_regression_of<X, Y, W, F>(xyws: &[(X, Y, W)]) -> Result<(F, F), LinRegError>
+where
+    X: Clone + Into<F>,
+    Y: Clone + Into<F>,
+    W: Clone + Into<F>,
+    F: Float,
+{
+    let xyws = || {
+        xyws.iter()
+            .map(|(x, y, w)| (x.clone().into(), y.clone().into(), w.clone().into()))
+    };
+
+    let (x_mean, y_mean) = weighted_means(xyws())?;
+    weighted_lin_reg(xyws(), x_mean, y_mean)
+}
+
+#[test]
+fn test_unit_weights() {
+    let xs: Vec<f64> = vec![1.0, 2.0, 3.0, 4.0, 5.0];
+    let ys: Vec<f64> = vec![2.0, 4.0, 5.0, 4.0, 5.0];
+    let ws: Vec<u8> = vec![1, 1, 1, 1, 1];
+
+    assert_eq!(Ok((0.6, 2.2)), weighted_linear_regression(&xs, &ys, &ws));
+}
+
+#[test]
+fn test_weights() {
+    // a zero weight removes the point, an integer weight repeats it
+    let xyws: Vec<(f64, f64, f64)> = vec![
+        (1.0, 2.0, 1.0),
+        (2.0, 4.0, 2.0),
+        (3.0, 5.0, 1.0),
+        (4.0, 4.0, 1.0),
+        (5.0, 5.0, 1.0),
+        (6.0, 100.0, 0.0),
+    ];
+    let (slope, intercept): (f64, f64) = weighted_linear_regression_of(&xyws).unwrap();
+
+    let xs: Vec<f64> = vec![1.0, 2.0, 2.0, 3.0, 4.0, 5.0];
+    let ys: Vec<f64> = vec![2.0, 4.0, 4.0, 5.0, 4.0, 5.0];
+    let (s, i): (f64, f64) = ::linear_regression(&xs, &ys).unwrap();
+
+    assert!((slope - s).abs() < 1e-12);
+    assert!((intercept - i).abs() < 1e-12);
+
+    // even a point at infinity
+    let xyws: Vec<(f64, f64, f64)> =
+        vec![(1.0, 1.0, 1.0), (2.0, 3.0, 1.0), (f64::INFINITY, 0.0, 0.0)];
+    assert_eq!(Ok((2.0, -1.0)), weighted_linear_regression_of(&xyws));
+}
+
+#[test]
+fn test_weight_errors() {
+    let xyws: Vec<(f64, f64, f64)> = vec![(1.0, 2.0, 1.0), (2.0, 4.0, -1.0)];
+    assert_eq!(
+        Err(LinRegError::NegativeWeight),
+        weighted_linear_regression_of::<_, _, _, f64>(&xyws)
+    );
+
+    let xyws: Vec<(f64, f64, f64)> = vec![(1.0, 2.0, 0.0), (2.0, 4.0, 0.0)];
+    assert_eq!(
+        Err(LinRegError::ZeroWeights),
+        weighted_linear_regression_of::<_, _, _, f64>(&xyws)
+    );
+
+    // only one point carries weight
+    let xyws: Vec<(f64, f64, f64)> = vec![(1.0, 2.0, 1.0), (2.0, 4.0, 0.0)];
+    assert_eq!(
+        Err(LinRegError::ConstantX),
+        weighted_linear_regression_of::<_, _, _, f64>(&xyws)
+    );
+
+    let xs: Vec<f64> = vec![1.0, 2.0];
+    assert_eq!(
+        Err(LinRegError::LengthMismatch),
+        weighted_linear_regression::<_, _, _, f64>(&xs, &xs, &xs[1..])
+    );
+}
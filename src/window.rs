@@ -0,0 +1,206 @@
+use num_traits::Float;
+
+#[cfg(test)]
+use std::vec::Vec;
+
+use error::LinRegError;
+use online::OnlineRegression;
+use result::RegressionResult;
+
+/// Linear regression over the last `N` data points
+///
+/// Stores the window in a fixed-size ring buffer and keeps an
+/// [`OnlineRegression`](struct.OnlineRegression.html) of its contents. Every
+/// new sample adds one point to the running sums and removes the oldest, so
+/// each step is O(1) and nothing is allocated.
+///
+/// To keep rounding errors from accumulating, the running sums are rebuilt
+/// from the buffer once every `N` samples, which is still O(1) amortized.
+///
+/// ```rust
+/// use linreg::SlidingWindowRegression;
+///
+/// let mut window = SlidingWindowRegression::<f64, 3>::new();
+/// window.push(1.0, 10.0).unwrap_err();
+/// window.push(2.0, 1.0).unwrap();
+/// window.push(3.0, 2.0).unwrap();
+///
+/// // the first point has left the window
+/// let (slope, intercept) = window.push(4.0, 3.0).unwrap();
+/// assert!((slope - 1.0).abs() < 1e-12);
+/// assert!((intercept + 1.0).abs() < 1e-12);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct SlidingWindowRegression<F, const N: usize> {
+    buffer: [(F, F); N],
+    // index of the oldest point in `buffer`
+    start: usize,
+    len: usize,
+    // samples since the running sums were last rebuilt
+    since_rebuild: usize,
+    // number of the newest points that share the newest x value
+    x_run: usize,
+    stats: OnlineRegression<F>,
+}
+
+impl<F: Float, const N: usize> Default for SlidingWindowRegression<F, N> {
+    fn default() -> Self {
+        SlidingWindowRegression::new()
+    }
+}
+
+impl<F: Float, const N: usize> SlidingWindowRegression<F, N> {
+    /// Creates an empty window
+    pub fn new() -> Self {
+        SlidingWindowRegression {
+            buffer: [(F::zero(), F::zero()); N],
+            start: 0,
+            len: 0,
+            since_rebuild: 0,
+            x_run: 0,
+            stats: OnlineRegression::new(),
+        }
+    }
+
+    /// Adds a data point, dropping the oldest one if the window is full
+    ///
+    /// Returns `Ok(slope, intercept)` of the regression line over the
+    /// window, or an error as described for
+    /// [`OnlineRegression::slope`](struct.OnlineRegression.html#method.slope).
+    /// A window with a capacity of zero always fails with `InvalidParameter`.
+    pub fn push(&mut self, x: F, y: F) -> Result<(F, F), LinRegError> {
+        if N == 0 {
+            return Err(LinRegError::InvalidParameter);
+        }
+
+        let newest = (self.start + self.len + N - 1) % N;
+        self.x_run = if self.len > 0 && self.buffer[newest].0 == x {
+            self.x_run.saturating_add(1)
+        } else {
+            1
+        };
+
+        if self.len == N {
+            let (old_x, old_y) = self.buffer[self.start];
+            self.stats.remove(old_x, old_y);
+            self.buffer[self.start] = (x, y);
+            self.start = (self.start + 1) % N;
+        } else {
+            self.buffer[(self.start + self.len) % N] = (x, y);
+            self.len += 1;
+        }
+        self.stats.push(x, y);
+
+        self.since_rebuild += 1;
+        if self.since_rebuild >= N {
+            self.rebuild();
+        }
+        if self.x_run == self.len {
+            return Err(LinRegError::ConstantX);
+        }
+
+        Ok((self.stats.slope()?, self.stats.intercept()?))
+    }
+
+    /// Removes all data points
+    pub fn clear(&mut self) {
+        self.start = 0;
+        self.len = 0;
+        self.since_rebuild = 0;
+        self.x_run = 0;
+        self.stats = OnlineRegression::new();
+    }
+
+    /// Number of data points in the window
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the window holds no data points
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` once the window holds `N` data points
+    pub fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    /// Iterates over the data points in the window, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = (F, F)> + '_ {
+        (0..self.len).map(move |i| self.buffer[(self.start + i) % N])
+    }
+
+    /// Running statistics of the points in the window
+    pub fn regression(&self) -> &OnlineRegression<F> {
+        &self.stats
+    }
+
+    /// Full regression statistics over the window
+    pub fn result(&self) -> Result<RegressionResult<F>, LinRegError> {
+        self.stats.result()
+    }
+
+    /// Recalculates the running sums from the buffered points
+    fn rebuild(&mut self) {
+        let mut stats = OnlineRegression::new();
+        for (x, y) in self.iter() {
+            stats.push(x, y);
+        }
+
+        self.stats = stats;
+        self.since_rebuild = 0;
+    }
+}
+
+#[test]
+fn test_window_matches_batch() {
+    let points: Vec<(f64, f64)> = (0..50)
+        .map(|i| {
+            let x = f64::from(i) * 0.5;
+            (x, 3.0 * x + f64::from(i % 7) - 1.0)
+        })
+        .collect();
+
+    let mut window = SlidingWindowRegression::<f64, 8>::new();
+    for (i, &(x, y)) in points.iter().enumerate() {
+        let line = window.push(x, y);
+        if i == 0 {
+            assert_eq!(Err(LinRegError::ConstantX), line);
+            continue;
+        }
+
+        let (slope, intercept) = line.unwrap();
+        let from = (i + 1).saturating_sub(8);
+        let (s, c): (f64, f64) = ::linear_regression_of(&points[from..=i]).unwrap();
+
+        assert!((slope - s).abs() < 1e-9);
+        assert!((intercept - c).abs() < 1e-9);
+    }
+
+    assert!(window.is_full());
+    assert_eq!(8, window.len());
+    assert_eq!(Some(points[42]), window.iter().next());
+}
+
+#[test]
+fn test_window_constant_x() {
+    // the window ends up holding only x = 3
+    let mut window = SlidingWindowRegression::<f64, 3>::new();
+    window.push(1.0, 0.0).unwrap_err();
+    window.push(2.0, 1.0).unwrap();
+    window.push(3.0, 5.0).unwrap();
+    window.push(3.0, 6.0).unwrap();
+    assert_eq!(Err(LinRegError::ConstantX), window.push(3.0, 7.0));
+    assert_eq!(Err(LinRegError::ConstantX), window.push(3.0, 8.0));
+
+    let (slope, intercept) = window.push(4.0, 9.0).unwrap();
+    assert!((slope - 1.5).abs() < 1e-12);
+    assert!((intercept - 3.0).abs() < 1e-12);
+}
+
+#[test]
+fn test_window_zero_capacity() {
+    let mut window = SlidingWindowRegression::<f32, 0>::new();
+    assert_eq!(Err(LinRegError::InvalidParameter), window.push(1.0, 1.0));
+}